@@ -0,0 +1,510 @@
+use std::{
+    fs::File,
+    io::Write,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use crate::{
+    camera::Camera,
+    canvas::Canvas,
+    color::Color,
+    error::{Error, Result},
+    light::PointLight,
+    material::{Material, ShadingModel},
+    patterns::Pattern,
+    quaternion::Decomposition,
+    tuple::{Point, Tuple, Vector},
+    util::RayTracerFloat,
+    world::World,
+};
+
+/// Values that can be blended between keyframes; `t` runs from 0 (self) to 1 (other)
+pub trait Interpolate {
+    fn interpolate(&self, other: &Self, t: RayTracerFloat) -> Self;
+}
+
+impl Interpolate for RayTracerFloat {
+    fn interpolate(&self, other: &Self, t: RayTracerFloat) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for Tuple {
+    fn interpolate(&self, other: &Self, t: RayTracerFloat) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Color {
+    fn interpolate(&self, other: &Self, t: RayTracerFloat) -> Self {
+        *self + (*other - *self) * t
+    }
+}
+
+impl Interpolate for Decomposition {
+    fn interpolate(&self, other: &Self, t: RayTracerFloat) -> Self {
+        Decomposition::interpolate(self, other, t)
+    }
+}
+
+impl Interpolate for Material {
+    /// Blends every numeric parameter, including PBR ones, and solid colors; any other pattern,
+    /// shading model or normal map switches over only once `t` reaches 1
+    fn interpolate(&self, other: &Self, t: RayTracerFloat) -> Self {
+        let pattern = match (&self.pattern, &other.pattern) {
+            (Pattern::Solid(a), Pattern::Solid(b)) => Pattern::Solid(a.interpolate(b, t)),
+            _ if t < 1. => self.pattern.clone(),
+            _ => other.pattern.clone(),
+        };
+
+        Material {
+            pattern,
+            ambient: self.ambient.interpolate(&other.ambient, t),
+            diffuse: self.diffuse.interpolate(&other.diffuse, t),
+            specular: self.specular.interpolate(&other.specular, t),
+            shininess: self.shininess.interpolate(&other.shininess, t),
+            reflective: self.reflective.interpolate(&other.reflective, t),
+            transparency: self.transparency.interpolate(&other.transparency, t),
+            refractive: self.refractive.interpolate(&other.refractive, t),
+            roughness: self.roughness.interpolate(&other.roughness, t),
+            roughness_samples: if t < 1. {
+                self.roughness_samples
+            } else {
+                other.roughness_samples
+            },
+            absorption: self.absorption.interpolate(&other.absorption, t),
+            dispersion: self.dispersion.interpolate(&other.dispersion, t),
+            casts_shadow: if t < 1. {
+                self.casts_shadow
+            } else {
+                other.casts_shadow
+            },
+            receives_shadow: if t < 1. {
+                self.receives_shadow
+            } else {
+                other.receives_shadow
+            },
+            shading: match (self.shading, other.shading) {
+                (
+                    ShadingModel::CookTorrance {
+                        metallic: m1,
+                        roughness: r1,
+                    },
+                    ShadingModel::CookTorrance {
+                        metallic: m2,
+                        roughness: r2,
+                    },
+                ) => ShadingModel::CookTorrance {
+                    metallic: m1.interpolate(&m2, t),
+                    roughness: r1.interpolate(&r2, t),
+                },
+                _ if t < 1. => self.shading,
+                _ => other.shading,
+            },
+            normal_map: if t < 1. {
+                self.normal_map.clone()
+            } else {
+                other.normal_map.clone()
+            },
+        }
+    }
+}
+
+/// How a value moves from one keyframe to the next
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum Easing {
+    /// holds the keyframe's value until the next keyframe
+    Step,
+    #[default]
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+}
+
+impl Easing {
+    /// Maps linear progress `t` in [0, 1] onto this curve
+    pub fn apply(&self, t: RayTracerFloat) -> RayTracerFloat {
+        match self {
+            Easing::Step => 0.,
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2. - t),
+            Easing::EaseInOut => t * t * (3. - 2. * t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keyframe<T> {
+    pub frame: RayTracerFloat,
+    pub value: T,
+    /// the curve used on the way to the following keyframe
+    pub easing: Easing,
+}
+
+/// A sequence of keyframes for one animated value
+#[derive(Debug, Clone, PartialEq)]
+pub struct Track<T> {
+    keyframes: Vec<Keyframe<T>>,
+}
+
+impl<T> Default for Track<T> {
+    fn default() -> Self {
+        Self { keyframes: vec![] }
+    }
+}
+
+impl<T: Interpolate + Clone> Track<T> {
+    pub fn constant(value: T) -> Self {
+        Self::default().key(0., value, Easing::Step)
+    }
+
+    /// Adds a keyframe, replacing any existing keyframe at the same frame
+    pub fn key(mut self, frame: RayTracerFloat, value: T, easing: Easing) -> Self {
+        self.keyframes.retain(|k| k.frame != frame);
+
+        let idx = self.keyframes.partition_point(|k| k.frame < frame);
+        self.keyframes.insert(
+            idx,
+            Keyframe {
+                frame,
+                value,
+                easing,
+            },
+        );
+
+        self
+    }
+
+    pub fn keyframes(&self) -> &Vec<Keyframe<T>> {
+        &self.keyframes
+    }
+
+    /// The value at `frame`, holding the first/last keyframe's value outside their range;
+    /// None if the track has no keyframes
+    pub fn value_at(&self, frame: RayTracerFloat) -> Option<T> {
+        let next_idx = self.keyframes.partition_point(|k| k.frame <= frame);
+
+        if next_idx == 0 {
+            return self.keyframes.first().map(|k| k.value.clone());
+        }
+
+        let prev = &self.keyframes[next_idx - 1];
+
+        let Some(next) = self.keyframes.get(next_idx) else {
+            return Some(prev.value.clone());
+        };
+
+        let t = (frame - prev.frame) / (next.frame - prev.frame);
+
+        Some(prev.value.interpolate(&next.value, prev.easing.apply(t)))
+    }
+}
+
+/// Camera placement for `Point::view_transform`, keyframed
+#[derive(Debug, Clone)]
+pub struct CameraAnimation {
+    pub hsize: usize,
+    pub vsize: usize,
+    pub field_of_view: RayTracerFloat,
+    pub from: Track<Point>,
+    pub to: Track<Point>,
+    pub up: Track<Vector>,
+}
+
+impl CameraAnimation {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: RayTracerFloat) -> Self {
+        Self {
+            hsize,
+            vsize,
+            field_of_view,
+            from: Track::constant(Point::point(0., 0., 0.)),
+            to: Track::constant(Point::point(0., 0., -1.)),
+            up: Track::constant(Vector::vector(0., 1., 0.)),
+        }
+    }
+
+    pub fn from(mut self, from: Track<Point>) -> Self {
+        self.from = from;
+        self
+    }
+
+    pub fn to(mut self, to: Track<Point>) -> Self {
+        self.to = to;
+        self
+    }
+
+    pub fn up(mut self, up: Track<Vector>) -> Self {
+        self.up = up;
+        self
+    }
+
+    pub fn camera_at(&self, frame: RayTracerFloat) -> Result<Camera> {
+        let from = self.from.value_at(frame).unwrap_or(Point::origin());
+        let to = self.to.value_at(frame).unwrap_or(Point::point(0., 0., -1.));
+        let up = self
+            .up
+            .value_at(frame)
+            .unwrap_or(Vector::vector(0., 1., 0.));
+
+        Camera::try_new(
+            self.hsize,
+            self.vsize,
+            self.field_of_view,
+            from.view_transform(&to, &up),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ObjectAnimation {
+    /// index into the base world's objects
+    index: usize,
+    transform: Option<Track<Decomposition>>,
+    material: Option<Track<Material>>,
+}
+
+/// A base world plus keyframed changes to it, rendered one frame at a time
+#[derive(Debug)]
+pub struct Animation {
+    world: World,
+    camera: CameraAnimation,
+    light_position: Option<Track<Point>>,
+    objects: Vec<ObjectAnimation>,
+}
+
+impl Animation {
+    pub fn new(world: World, camera: CameraAnimation) -> Self {
+        Self {
+            world,
+            camera,
+            light_position: None,
+            objects: vec![],
+        }
+    }
+
+    pub fn light_position(mut self, track: Track<Point>) -> Self {
+        self.light_position = Some(track);
+        self
+    }
+
+    fn object_animation(&mut self, index: usize) -> &mut ObjectAnimation {
+        let pos = match self.objects.iter().position(|o| o.index == index) {
+            Some(pos) => pos,
+            None => {
+                self.objects.push(ObjectAnimation {
+                    index,
+                    transform: None,
+                    material: None,
+                });
+                self.objects.len() - 1
+            }
+        };
+
+        &mut self.objects[pos]
+    }
+
+    /// Keyframes the transform of the world's `index`th top-level object
+    pub fn object_transform(mut self, index: usize, track: Track<Decomposition>) -> Self {
+        self.object_animation(index).transform = Some(track);
+        self
+    }
+
+    /// Keyframes the material of the world's `index`th top-level object
+    pub fn object_material(mut self, index: usize, track: Track<Material>) -> Self {
+        self.object_animation(index).material = Some(track);
+        self
+    }
+
+    pub fn world_at(&self, frame: RayTracerFloat) -> Result<World> {
+        let mut objects = self.world.objects.clone();
+
+        for anim in &self.objects {
+            let Some(object) = objects.get(anim.index) else {
+                return Err(Error::NoSuchObject(anim.index));
+            };
+
+            let transform = match anim.transform.as_ref().and_then(|t| t.value_at(frame)) {
+                Some(d) => d.to_transform(),
+                None => *object.transform(),
+            };
+
+            let material = anim
+                .material
+                .as_ref()
+                .and_then(|m| m.value_at(frame))
+                .unwrap_or_else(|| object.material.clone());
+
+            objects[anim.index] = object.try_copy_with(transform, material)?;
+        }
+
+        let light_source = match self.light_position.as_ref().and_then(|l| l.value_at(frame)) {
+            Some(position) => PointLight::new(position, self.world.light_source.intensity),
+            None => self.world.light_source,
+        };
+
+        Ok(World::new(objects, light_source).with_background(self.world.background.clone()))
+    }
+
+    pub fn camera_at(&self, frame: RayTracerFloat) -> Result<Camera> {
+        self.camera.camera_at(frame)
+    }
+
+    pub fn render_frame(&self, frame: usize) -> Result<Canvas> {
+        let frame = frame as RayTracerFloat;
+        let world = Rc::new(self.world_at(frame)?);
+        Ok(self.camera_at(frame)?.render(&world))
+    }
+
+    /// Renders every frame in `frames` to `<dir>/<prefix><frame, zero-padded>.ppm`, returning
+    /// the paths written
+    pub fn render_sequence(
+        &self,
+        frames: RangeInclusive<usize>,
+        dir: &Path,
+        prefix: &str,
+    ) -> Result<Vec<PathBuf>> {
+        let mut written = vec![];
+
+        for frame in frames {
+            let image = self.render_frame(frame)?;
+            let path = dir.join(format!("{}{:04}.ppm", prefix, frame));
+
+            File::create(&path)
+                .and_then(|mut f| write!(f, "{}", image.to_ppm().whole_file()))
+                .map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))?;
+
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{f64::consts::FRAC_PI_2, fs};
+
+    use approx::assert_abs_diff_eq;
+
+    use crate::{
+        color::Color,
+        material::MaterialBuilder,
+        quaternion::Decomposition,
+        transforms::translation,
+        tuple::{Point, Vector},
+        world::World,
+    };
+
+    use super::{Animation, CameraAnimation, Easing, Track};
+
+    #[test]
+    fn easing_curves_hit_endpoints() {
+        for e in [
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(e.apply(0.), 0.);
+            assert_eq!(e.apply(1.), 1.);
+        }
+
+        assert_eq!(Easing::Step.apply(0.99), 0.);
+        assert!(Easing::EaseIn.apply(0.5) < 0.5);
+        assert!(Easing::EaseOut.apply(0.5) > 0.5);
+    }
+
+    #[test]
+    fn track_interpolates_and_clamps() {
+        let track = Track::default()
+            .key(10., 2.0, Easing::Linear)
+            .key(0., 0.0, Easing::Linear)
+            .key(20., 0.0, Easing::Step);
+
+        assert_eq!(track.value_at(-5.), Some(0.));
+        assert_eq!(track.value_at(5.), Some(1.));
+        assert_eq!(track.value_at(10.), Some(2.));
+        assert_eq!(track.value_at(15.), Some(1.));
+        assert_eq!(track.value_at(25.), Some(0.));
+        assert_eq!(Track::<f64>::default().value_at(0.), None);
+    }
+
+    #[test]
+    fn world_at_applies_keyframes() {
+        let anim = Animation::new(
+            World::default_world(),
+            CameraAnimation::new(10, 10, FRAC_PI_2),
+        )
+        .light_position(
+            Track::default()
+                .key(0., Point::point(0., 0., 0.), Easing::Linear)
+                .key(10., Point::point(10., 0., 0.), Easing::Linear),
+        )
+        .object_transform(
+            1,
+            Track::default()
+                .key(0., Decomposition::default(), Easing::Linear)
+                .key(
+                    10.,
+                    Decomposition::decompose(&translation(0., 4., 0.)).unwrap(),
+                    Easing::Linear,
+                ),
+        )
+        .object_material(
+            0,
+            Track::default()
+                .key(0., MaterialBuilder::default().build(), Easing::Linear)
+                .key(
+                    10.,
+                    MaterialBuilder::default()
+                        .color(Color::new(0., 0., 0.))
+                        .ambient(0.3)
+                        .build(),
+                    Easing::Linear,
+                ),
+        );
+
+        let w = anim.world_at(5.).unwrap();
+
+        assert_abs_diff_eq!(w.light_source.position, Point::point(5., 0., 0.));
+        assert_abs_diff_eq!(*w.objects[1].transform(), translation(0., 2., 0.));
+        assert_abs_diff_eq!(w.objects[0].material.ambient, 0.2);
+        assert_eq!(
+            w.objects[0].material.pattern,
+            crate::patterns::Pattern::Solid(Color::new(0.5, 0.5, 0.5))
+        );
+    }
+
+    #[test]
+    fn render_sequence_writes_numbered_frames() {
+        let camera = CameraAnimation::new(4, 4, FRAC_PI_2)
+            .from(
+                Track::default()
+                    .key(0., Point::point(0., 0., -5.), Easing::Linear)
+                    .key(2., Point::point(5., 0., 0.), Easing::Linear),
+            )
+            .to(Track::constant(Point::origin()))
+            .up(Track::constant(Vector::vector(0., 1., 0.)));
+
+        let anim = Animation::new(World::default_world(), camera);
+
+        let dir = std::env::temp_dir().join(format!("rtc-animation-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let paths = anim.render_sequence(1..=2, &dir, "frame").unwrap();
+
+        assert_eq!(
+            paths,
+            vec![dir.join("frame0001.ppm"), dir.join("frame0002.ppm")]
+        );
+        assert!(fs::read_to_string(&paths[0])
+            .unwrap()
+            .starts_with("P3\n4 4\n"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,120 @@
+use crate::{
+    color::{Color, BLACK},
+    patterns::uv::{CubeFace, UvMapping, UvPattern},
+    tuple::{Point, Vector},
+};
+
+/// What a ray sees when it escapes the scene without hitting anything
+#[derive(Debug, PartialEq, Clone)]
+pub enum Background {
+    Solid(Color),
+    /// blends from `bottom` straight down to `top` straight up
+    Gradient {
+        bottom: Color,
+        top: Color,
+    },
+    /// a panorama wrapped around the scene, u following longitude and v latitude
+    Equirectangular(UvPattern),
+    /// six faces of a skybox, in `CubeFace` order
+    CubeMap(Box<[UvPattern; 6]>),
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Solid(BLACK)
+    }
+}
+
+impl Background {
+    pub fn cube_map(faces: [UvPattern; 6]) -> Self {
+        Background::CubeMap(Box::new(faces))
+    }
+
+    /// The color seen looking along `direction`, which need not be normalized
+    pub fn color_for(&self, direction: &Vector) -> Color {
+        let d = direction.normalize();
+
+        match self {
+            Background::Solid(c) => *c,
+            Background::Gradient { bottom, top } => {
+                *bottom + (*top - *bottom) * ((d.y() + 1.) / 2.)
+            }
+            Background::Equirectangular(pattern) => {
+                let (u, v) = UvMapping::Spherical.map(&Point::point(d.x(), d.y(), d.z()));
+                pattern.color_at(u, v)
+            }
+            Background::CubeMap(faces) => {
+                // push the direction out onto the surface of the [-1, 1] cube
+                let max = d.x().abs().max(d.y().abs()).max(d.z().abs());
+                let p = Point::point(d.x() / max, d.y() / max, d.z() / max);
+                let face = CubeFace::of(&p);
+                let (u, v) = face.uv(&p);
+                faces[face as usize].color_at(u, v)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+
+    use crate::{
+        color::{Color, BLACK, WHITE},
+        patterns::uv::UvPattern,
+        tuple::Vector,
+    };
+
+    use super::Background;
+
+    #[test]
+    fn gradient_follows_elevation() {
+        let bg = Background::Gradient {
+            bottom: BLACK,
+            top: WHITE,
+        };
+
+        assert_abs_diff_eq!(bg.color_for(&Vector::vector(0., -2., 0.)), BLACK);
+        assert_abs_diff_eq!(
+            bg.color_for(&Vector::vector(1., 0., 0.)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_abs_diff_eq!(bg.color_for(&Vector::vector(0., 1., 0.)), WHITE);
+    }
+
+    #[test]
+    fn equirectangular_poles() {
+        // one column, split into a southern and a northern half
+        let bg = Background::Equirectangular(UvPattern::checkers(1., 2., BLACK, WHITE));
+
+        assert_eq!(bg.color_for(&Vector::vector(0.1, 1., 0.)), WHITE);
+        assert_eq!(bg.color_for(&Vector::vector(0.1, -1., 0.)), BLACK);
+        assert_eq!(bg.color_for(&Vector::vector(1., 0.1, 0.)), WHITE);
+        assert_eq!(bg.color_for(&Vector::vector(1., -0.1, 0.)), BLACK);
+    }
+
+    #[test]
+    fn cube_map_picks_face_from_direction() {
+        let colors = [
+            Color::new(1., 0., 0.),
+            Color::new(0., 1., 0.),
+            Color::new(0., 0., 1.),
+            Color::new(1., 1., 0.),
+            Color::new(0., 1., 1.),
+            Color::new(1., 0., 1.),
+        ];
+
+        let bg = Background::cube_map(colors.map(|c| UvPattern::align_check(c, c, c, c, c)));
+
+        for (direction, color) in [
+            (Vector::vector(-5., 1., 2.), colors[0]),
+            (Vector::vector(1., 2., 5.), colors[1]),
+            (Vector::vector(5., 1., 2.), colors[2]),
+            (Vector::vector(1., 2., -5.), colors[3]),
+            (Vector::vector(1., 5., 2.), colors[4]),
+            (Vector::vector(1., -5., 2.), colors[5]),
+        ] {
+            assert_eq!(bg.color_for(&direction), color);
+        }
+    }
+}
@@ -67,25 +67,17 @@ pub fn basic_ray_trace(filename: &Path, transform: Transform) -> Result<()> {
 
 #[allow(unused_variables)]
 pub fn basic_scene() -> World {
-    let gradient = Pattern::Gradient {
-        transform: identity(),
-        start: Color::new(1., 0., 0.),
-        end: WHITE,
-    };
+    let gradient = Pattern::gradient(identity(), Color::new(1., 0., 0.), WHITE);
 
     let floor = Object::plane(
         identity(),
-        MaterialBuilder::default().pattern(gradient).build(),
+        MaterialBuilder::default().pattern(gradient.clone()).build(),
     );
 
     let middle_sphere = Object::sphere(
         translation(-0.5, 1., 0.5),
         MaterialBuilder::default()
-            .pattern(Pattern::Stripe {
-                transform: identity(),
-                even: Color::new(1., 0., 0.),
-                odd: WHITE,
-            })
+            .pattern(Pattern::stripe(identity(), Color::new(1., 0., 0.), WHITE))
             .diffuse(0.7)
             .specular(0.3)
             .reflective(0.8)
@@ -163,10 +155,9 @@ pub fn basic_scene() -> World {
 }
 
 pub fn render_scene_to_file(world: World, camera: Camera, filename: &Path) -> Result<()> {
+    let (image, stats) = camera.render_with_stats(&Rc::new(world));
+    println!("{}: {}", filename.display(), stats);
+
     let mut output = File::create(filename)?;
-    write!(
-        output,
-        "{}",
-        camera.render(&Rc::new(world)).to_ppm().whole_file()
-    )
+    write!(output, "{}", image.to_ppm().whole_file())
 }
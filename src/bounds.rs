@@ -0,0 +1,186 @@
+use std::mem::swap;
+
+use crate::{ray::Ray, transforms::Transform, tuple::Point, util::RayTracerFloat};
+
+const INFINITY: RayTracerFloat = RayTracerFloat::INFINITY;
+
+/// An axis-aligned box, which may be unbounded along any axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Bounds {
+    pub const fn new(min: Point, max: Point) -> Self {
+        Self { min, max }
+    }
+
+    /// A box containing nothing, which leaves any box it is combined with unchanged
+    pub const fn empty() -> Self {
+        Self::new(
+            Point::point(INFINITY, INFINITY, INFINITY),
+            Point::point(-INFINITY, -INFINITY, -INFINITY),
+        )
+    }
+
+    pub const fn infinite() -> Self {
+        Self::new(
+            Point::point(-INFINITY, -INFINITY, -INFINITY),
+            Point::point(INFINITY, INFINITY, INFINITY),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x() > self.max.x() || self.min.y() > self.max.y() || self.min.z() > self.max.z()
+    }
+
+    pub fn contains(&self, p: &Point) -> bool {
+        (self.min.x()..=self.max.x()).contains(&p.x())
+            && (self.min.y()..=self.max.y()).contains(&p.y())
+            && (self.min.z()..=self.max.z()).contains(&p.z())
+    }
+
+    /// The smallest box containing both boxes
+    pub fn union(&self, other: &Bounds) -> Bounds {
+        Bounds::new(
+            Point::point(
+                self.min.x().min(other.min.x()),
+                self.min.y().min(other.min.y()),
+                self.min.z().min(other.min.z()),
+            ),
+            Point::point(
+                self.max.x().max(other.max.x()),
+                self.max.y().max(other.max.y()),
+                self.max.z().max(other.max.z()),
+            ),
+        )
+    }
+
+    /// The smallest axis-aligned box containing this one once transformed. Boxes that are
+    /// unbounded along any axis stay unbounded along every axis.
+    pub fn transform(&self, transform: &Transform) -> Bounds {
+        if self.is_empty() {
+            return *self;
+        }
+
+        let corners = [self.min, self.max];
+        if corners
+            .iter()
+            .any(|c| !(c.x().is_finite() && c.y().is_finite() && c.z().is_finite()))
+        {
+            return Bounds::infinite();
+        }
+
+        let mut bounds = Bounds::empty();
+        for x in [self.min.x(), self.max.x()] {
+            for y in [self.min.y(), self.max.y()] {
+                for z in [self.min.z(), self.max.z()] {
+                    let p = Point::point(x, y, z).transform(transform);
+                    bounds = bounds.union(&Bounds::new(p, p));
+                }
+            }
+        }
+
+        bounds
+    }
+
+    /// The range of t over which `ray` is inside the box, if it passes through it at all
+    pub fn span(&self, ray: &Ray) -> Option<(RayTracerFloat, RayTracerFloat)> {
+        let mut tmin = -INFINITY;
+        let mut tmax = INFINITY;
+
+        let coords = |p: &Point| [p.x(), p.y(), p.z()];
+        let (origin, direction) = (coords(&ray.origin), coords(&ray.direction));
+        let (min, max) = (coords(&self.min), coords(&self.max));
+
+        for axis in 0..3 {
+            let (origin, direction) = (origin[axis], direction[axis]);
+            let (min, max) = (min[axis], max[axis]);
+
+            if direction == 0. {
+                if origin < min || origin > max {
+                    return None;
+                }
+                continue;
+            }
+
+            let mut near = (min - origin) / direction;
+            let mut far = (max - origin) / direction;
+            if near > far {
+                swap(&mut near, &mut far);
+            }
+
+            tmin = tmin.max(near);
+            tmax = tmax.min(far);
+        }
+
+        (tmin <= tmax).then_some((tmin, tmax))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::FRAC_PI_4;
+
+    use approx::assert_abs_diff_eq;
+
+    use crate::{
+        ray::Ray,
+        transforms::{rotation, translation, RotationAxis},
+        tuple::{Point, Vector},
+    };
+
+    use super::Bounds;
+
+    fn unit() -> Bounds {
+        Bounds::new(Point::point(-1., -1., -1.), Point::point(1., 1., 1.))
+    }
+
+    #[test]
+    fn union_and_containment() {
+        let b = unit().union(&Bounds::new(
+            Point::point(0., 0., 0.),
+            Point::point(3., 2., 1.),
+        ));
+
+        assert_eq!(
+            b,
+            Bounds::new(Point::point(-1., -1., -1.), Point::point(3., 2., 1.))
+        );
+        assert!(b.contains(&Point::point(2.5, 1.5, 0.)));
+        assert!(!b.contains(&Point::point(2.5, 2.5, 0.)));
+        assert_eq!(Bounds::empty().union(&unit()), unit());
+        assert!(Bounds::empty().is_empty());
+    }
+
+    #[test]
+    fn transformed_bounds() {
+        let b = unit().transform(&(translation(1., 0., 0.) * rotation(RotationAxis::Y, FRAC_PI_4)));
+        let r = 2f64.sqrt();
+
+        assert_abs_diff_eq!(b.min, Point::point(1. - r, -1., -r));
+        assert_abs_diff_eq!(b.max, Point::point(1. + r, 1., r));
+
+        let plane = Bounds::new(
+            Point::point(-f64::INFINITY, 0., -f64::INFINITY),
+            Point::point(f64::INFINITY, 0., f64::INFINITY),
+        );
+        assert_eq!(
+            plane.transform(&translation(0., 1., 0.)),
+            Bounds::infinite()
+        );
+    }
+
+    #[test]
+    fn rays_through_bounds() {
+        let hits = Ray::new(Point::point(-5., 0.5, 0.), Vector::vector(1., 0., 0.));
+        assert_eq!(unit().span(&hits), Some((4., 6.)));
+
+        let misses = Ray::new(Point::point(-5., 2., 0.), Vector::vector(1., 0., 0.));
+        assert_eq!(unit().span(&misses), None);
+
+        let diagonal = Ray::new(Point::point(-5., -5., 0.), Vector::vector(1., 1., 0.));
+        assert_eq!(unit().span(&diagonal), Some((4., 6.)));
+    }
+}
@@ -1,7 +1,14 @@
-use std::rc::Rc;
+use std::{f64::consts::PI, rc::Rc};
 
 use crate::{
-    canvas::Canvas, ray::Ray, transforms::Transform, tuple::Point, util::RayTracerFloat,
+    canvas::Canvas,
+    error::{Error, Result},
+    intersection::IntersectionBuffer,
+    ray::Ray,
+    stats::{self, RayKind, RenderStats},
+    transforms::{InvertibleTransform, Transform},
+    tuple::Point,
+    util::RayTracerFloat,
     world::World,
 };
 
@@ -10,7 +17,7 @@ pub struct Camera {
     pub hsize: usize,
     pub vsize: usize,
     pub field_of_view: RayTracerFloat,
-    pub transform: Transform,
+    transform: InvertibleTransform,
     pub half_width: RayTracerFloat,
     pub half_height: RayTracerFloat,
     pub pixel_size: RayTracerFloat,
@@ -23,6 +30,29 @@ impl Camera {
         field_of_view: RayTracerFloat,
         transform: Transform,
     ) -> Self {
+        Self::try_new(hsize, vsize, field_of_view, transform)
+            .unwrap_or_else(|e| panic!("invalid camera: {}", e))
+    }
+
+    pub fn try_new(
+        hsize: usize,
+        vsize: usize,
+        field_of_view: RayTracerFloat,
+        transform: Transform,
+    ) -> Result<Self> {
+        if hsize == 0 || vsize == 0 {
+            return Err(Error::InvalidDimensions {
+                width: hsize,
+                height: vsize,
+            });
+        }
+
+        if !(field_of_view > 0. && field_of_view < PI) {
+            return Err(Error::InvalidFieldOfView(field_of_view));
+        }
+
+        let transform = InvertibleTransform::new(transform)?;
+
         let half_view = (field_of_view / 2.).tan();
         let aspect = (hsize as RayTracerFloat) / (vsize as RayTracerFloat);
 
@@ -34,7 +64,7 @@ impl Camera {
 
         let pixel_size = (half_width * 2.) / (hsize as RayTracerFloat);
 
-        Self {
+        Ok(Self {
             hsize,
             vsize,
             field_of_view,
@@ -42,7 +72,17 @@ impl Camera {
             half_width,
             half_height,
             pixel_size,
-        }
+        })
+    }
+
+    pub fn transform(&self) -> &Transform {
+        self.transform.matrix()
+    }
+
+    /// Replaces the camera's transform, leaving it unchanged if `transform` is not invertible
+    pub fn set_transform(&mut self, transform: Transform) -> Result<()> {
+        self.transform = InvertibleTransform::new(transform)?;
+        Ok(())
     }
 
     pub fn ray_for_pixel(&self, x: usize, y: usize) -> Ray {
@@ -58,10 +98,7 @@ impl Camera {
         // # using the camera matrix, transform the canvas point and the origin,
         // # and then compute the ray's direction vector.
         // # (remember that the canvas is at z=-1)
-        let xform_inv = &self
-            .transform
-            .try_inverse()
-            .expect("cannot invert camera transform");
+        let xform_inv = self.transform.inverse();
 
         let pixel = Point::point(world_x, world_y, -1.).transform(xform_inv);
         let origin = Point::point(0., 0., 0.).transform(xform_inv);
@@ -71,15 +108,55 @@ impl Camera {
 
     pub fn render(&self, world: &Rc<World>) -> Canvas {
         let mut image = Canvas::new(self.hsize, self.vsize);
+        let mut buf = IntersectionBuffer::new();
 
         for y in 0..self.vsize {
             for x in 0..self.hsize {
                 let ray = self.ray_for_pixel(x, y);
-                let color = world.color_at(&ray, 5);
+                stats::record_ray(RayKind::Primary);
+                let color = world.color_at_buffered(&ray, 5, &mut buf);
                 image.write(x, y, color);
             }
         }
 
         image
     }
+
+    /// Renders `world` as `render` does, also returning the statistics collected along the way
+    pub fn render_with_stats(&self, world: &Rc<World>) -> (Canvas, RenderStats) {
+        stats::collect(|| self.render(world))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    use crate::{
+        error::Error,
+        transforms::{identity, scaling},
+    };
+
+    use super::Camera;
+
+    #[test]
+    fn try_new_rejects_invalid_cameras() {
+        assert_eq!(
+            Camera::try_new(0, 100, FRAC_PI_2, identity()),
+            Err(Error::InvalidDimensions {
+                width: 0,
+                height: 100
+            })
+        );
+
+        assert_eq!(
+            Camera::try_new(100, 100, PI, identity()),
+            Err(Error::InvalidFieldOfView(PI))
+        );
+
+        assert_eq!(
+            Camera::try_new(100, 100, FRAC_PI_2, scaling(0., 0., 0.)),
+            Err(Error::NonInvertibleTransform)
+        );
+    }
 }
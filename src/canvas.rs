@@ -1,4 +1,7 @@
+use std::{fs, path::Path};
+
 use crate::color::Color;
+use crate::error::{Error, Result};
 
 mod ppm;
 
@@ -27,8 +30,29 @@ impl Canvas {
         Canvas { pixels: rows }
     }
 
+    /// Creates a new width * height Canvas with every pixel black, rejecting empty dimensions
+    pub fn try_new(width: usize, height: usize) -> Result<Canvas> {
+        if width == 0 || height == 0 {
+            return Err(Error::InvalidDimensions { width, height });
+        }
+
+        Ok(Canvas::new(width, height))
+    }
+
+    /// Reads a canvas back from the contents of a plain (P3) PPM file
+    pub fn from_ppm(contents: &str) -> Result<Canvas> {
+        ppm::parse(contents)
+    }
+
+    pub fn load_ppm(path: &Path) -> Result<Canvas> {
+        let contents = fs::read_to_string(path)
+            .map_err(|e| Error::Io(format!("{}: {}", path.display(), e)))?;
+
+        Canvas::from_ppm(&contents)
+    }
+
     pub fn width(&self) -> usize {
-        self.pixels[0].len()
+        self.pixels.first().map_or(0, Vec::len)
     }
 
     pub fn height(&self) -> usize {
@@ -78,3 +102,22 @@ impl Canvas {
         ppm
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::error::Error;
+
+    use super::Canvas;
+
+    #[test]
+    fn empty_canvas_has_zero_width() {
+        assert_eq!(Canvas::default().width(), 0);
+        assert!(matches!(
+            Canvas::try_new(0, 10),
+            Err(Error::InvalidDimensions {
+                width: 0,
+                height: 10
+            })
+        ));
+    }
+}
@@ -1,3 +1,10 @@
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    error::{Error, Result},
+    util::RayTracerFloat,
+};
+
 #[derive(Debug)]
 pub struct Ppm {
     lines: Vec<String>,
@@ -25,3 +32,79 @@ impl Ppm {
         self.lines.join("")
     }
 }
+
+/// Parses a plain (P3) PPM file; comments are allowed anywhere and values may be split across
+/// lines arbitrarily
+pub(crate) fn parse(contents: &str) -> Result<Canvas> {
+    let mut tokens = contents
+        .lines()
+        .map(|l| l.split('#').next().unwrap_or_default())
+        .flat_map(str::split_whitespace);
+
+    match tokens.next() {
+        Some("P3") => (),
+        Some(magic) => {
+            return Err(Error::InvalidPpm(format!(
+                "expected magic number P3 but got {}",
+                magic
+            )))
+        }
+        None => return Err(Error::InvalidPpm("file is empty".into())),
+    }
+
+    let mut next_number = |what: &str| -> Result<usize> {
+        let token = tokens
+            .next()
+            .ok_or_else(|| Error::InvalidPpm(format!("missing {}", what)))?;
+
+        token
+            .parse()
+            .map_err(|_| Error::InvalidPpm(format!("invalid {}: {}", what, token)))
+    };
+
+    let width = next_number("width")?;
+    let height = next_number("height")?;
+    let max = next_number("maximum color value")?;
+
+    if max == 0 {
+        return Err(Error::InvalidPpm(
+            "maximum color value must be positive".into(),
+        ));
+    }
+
+    let pixels = width
+        .checked_mul(height)
+        .ok_or_else(|| Error::InvalidPpm(format!("{}x{} is too large", width, height)))?;
+
+    let mut next_channel = |what: &str| -> Result<RayTracerFloat> {
+        let value = next_number(what)?;
+
+        if value > max {
+            return Err(Error::InvalidPpm(format!(
+                "{} {} exceeds the maximum color value {}",
+                what, value, max
+            )));
+        }
+
+        Ok(value as RayTracerFloat / max as RayTracerFloat)
+    };
+
+    // the header is untrusted, so only size the canvas once the pixels have actually been read
+    let mut colors = vec![];
+
+    while colors.len() < pixels {
+        let r = next_channel("red value")?;
+        let g = next_channel("green value")?;
+        let b = next_channel("blue value")?;
+
+        colors.push(Color::new(r, g, b));
+    }
+
+    let mut canvas = Canvas::try_new(width, height)?;
+
+    for (i, c) in colors.into_iter().enumerate() {
+        canvas.write(i % width, i / width, c);
+    }
+
+    Ok(canvas)
+}
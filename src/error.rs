@@ -0,0 +1,73 @@
+use std::fmt::Display;
+
+use crate::{tuple::Tuple, util::RayTracerFloat};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    NonInvertibleTransform,
+    NotAPoint(Tuple),
+    NotAVector(Tuple),
+    InvalidDimensions {
+        width: usize,
+        height: usize,
+    },
+    InvalidFieldOfView(RayTracerFloat),
+    InvalidBounds {
+        min_y: RayTracerFloat,
+        max_y: RayTracerFloat,
+    },
+    DegenerateTriangle,
+    InvalidRadius(RayTracerFloat),
+    NotAGroup,
+    NoSuchChild(usize),
+    NoObjectAt(String),
+    InvalidParent(String),
+    NoSuchObject(usize),
+    Io(String),
+    InvalidPpm(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::NonInvertibleTransform => f.write_str("transform is not invertible"),
+            Error::NotAPoint(t) => f.write_fmt(format_args!("expected a point but got {}", t)),
+            Error::NotAVector(t) => f.write_fmt(format_args!("expected a vector but got {}", t)),
+            Error::InvalidDimensions { width, height } => f.write_fmt(format_args!(
+                "dimensions must be non-zero but were {}x{}",
+                width, height
+            )),
+            Error::InvalidFieldOfView(fov) => f.write_fmt(format_args!(
+                "field of view must be between 0 and π radians but was {}",
+                fov
+            )),
+            Error::InvalidBounds { min_y, max_y } => f.write_fmt(format_args!(
+                "min_y ({}) must not be greater than max_y ({})",
+                min_y, max_y
+            )),
+            Error::DegenerateTriangle => f.write_str("triangle vertices are collinear"),
+            Error::InvalidRadius(r) => {
+                f.write_fmt(format_args!("radius must be positive but was {}", r))
+            }
+            Error::NotAGroup => f.write_str("object is not a group"),
+            Error::NoSuchChild(index) => {
+                f.write_fmt(format_args!("group has no child at index {}", index))
+            }
+            Error::NoObjectAt(path) => {
+                f.write_fmt(format_args!("world has no object at path {}", path))
+            }
+            Error::InvalidParent(path) => {
+                f.write_fmt(format_args!("cannot move {} beneath itself", path))
+            }
+            Error::NoSuchObject(index) => {
+                f.write_fmt(format_args!("world has no object at index {}", index))
+            }
+            Error::Io(msg) => f.write_fmt(format_args!("I/O error: {}", msg)),
+            Error::InvalidPpm(msg) => f.write_fmt(format_args!("invalid PPM: {}", msg)),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -1,3 +1,4 @@
 pub(crate) mod clock_face;
 pub(crate) mod hexagon_scene;
+pub(crate) mod turntable;
 pub(crate) mod virtual_cannon;
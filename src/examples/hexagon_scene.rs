@@ -6,21 +6,22 @@ use std::{
 use crate::{
     material::Material,
     objects::Object,
-    transforms::{rotation, scaling, translation, RotationAxis, Transform},
+    transforms::{identity, Transform, TransformBuilder},
 };
 
 fn hexagon_corner() -> Object {
     Object::sphere(
-        translation(0., 0., -1.) * scaling(0.25, 0.25, 0.25),
+        identity().scale_by(0.25, 0.25, 0.25).translate(0., 0., -1.),
         Material::default(),
     )
 }
 
 fn hexagon_edge() -> Object {
-    let transform = translation(0.0, 0.0, -1.0)
-        * rotation(RotationAxis::Y, -FRAC_PI_6)
-        * rotation(RotationAxis::Z, -FRAC_PI_2)
-        * scaling(0.25, 1.0, 0.25);
+    let transform = identity()
+        .scale_by(0.25, 1.0, 0.25)
+        .rotate_z(-FRAC_PI_2)
+        .rotate_y(-FRAC_PI_6)
+        .translate(0.0, 0.0, -1.0);
 
     Object::cylinder(transform, Material::default(), 0.0, 1.0, false)
 }
@@ -36,14 +37,11 @@ pub fn hexagon() -> Rc<Object> {
     let mut sides: Vec<Rc<Object>> = vec![];
 
     for n in 0..=5 {
-        sides.push(hexagon_side(rotation(
-            RotationAxis::Y,
-            (n as f64) * FRAC_PI_3,
-        )));
+        sides.push(hexagon_side(identity().rotate_y((n as f64) * FRAC_PI_3)));
     }
 
     Object::group(
-        translation(0.0, 0.8, 0.0) * rotation(RotationAxis::X, -FRAC_PI_6),
+        identity().rotate_x(-FRAC_PI_6).translate(0.0, 0.8, 0.0),
         sides,
     )
 }
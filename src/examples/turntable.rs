@@ -0,0 +1,76 @@
+use std::{f64::consts::FRAC_PI_3, path::Path};
+
+use ray_tracer_challenge_rs::{
+    animation::{Animation, CameraAnimation, Easing, Track},
+    color::Color,
+    error::Result,
+    light::PointLight,
+    quaternion::Decomposition,
+    transforms::{rotation, RotationAxis, Transform},
+    tuple::{Point, Vector},
+    world::World,
+};
+
+use crate::examples::hexagon_scene::hexagon;
+
+/// Spins the hexagon one full turn over 48 frames while the camera eases in
+pub fn hexagon_turntable(dir: &Path) -> Result<()> {
+    let world = World::new(
+        vec![hexagon()],
+        PointLight::new(Point::point(-10., 10., -10.), Color::new(1., 1., 1.)),
+    );
+
+    let spin = spin(world.objects[0].transform(), 48.)?;
+
+    let camera = CameraAnimation::new(300, 150, FRAC_PI_3)
+        .from(
+            Track::default()
+                .key(0., Point::point(0., 1.5, -6.), Easing::EaseInOut)
+                .key(48., Point::point(0., 1.5, -4.), Easing::Linear),
+        )
+        .to(Track::constant(Point::point(0., 0., 0.)))
+        .up(Track::constant(Vector::vector(0., 1., 0.)));
+
+    Animation::new(world, camera)
+        .object_transform(0, spin)
+        .render_sequence(0..=47, dir, "turntable_")?;
+
+    Ok(())
+}
+
+/// Keys `base` turning once about its own y axis over `frames` frames. Keys replace the
+/// object's transform outright, so each one carries `base` along with the turn.
+fn spin(base: &Transform, frames: f64) -> Result<Track<Decomposition>> {
+    // slerp takes the shortest arc, so a full turn needs intermediate keys
+    (0..=3).try_fold(Track::default(), |track, i| {
+        let turn = rotation(RotationAxis::Y, i as f64 * 2. * FRAC_PI_3);
+        Ok(track.key(
+            i as f64 * frames / 3.,
+            Decomposition::decompose(&(base * turn))?,
+            Easing::Linear,
+        ))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+    use ray_tracer_challenge_rs::transforms::{identity, TransformBuilder};
+
+    use super::spin;
+
+    #[test]
+    fn spin_starts_from_the_object_transform() {
+        let base = identity().rotate_x(-0.5).translate(0., 0.8, 0.);
+        let track = spin(&base, 48.).unwrap();
+
+        // a whole turn comes back to where it started
+        for frame in [0., 48.] {
+            assert_abs_diff_eq!(
+                track.value_at(frame).unwrap().to_transform(),
+                base,
+                epsilon = 1e-9
+            );
+        }
+    }
+}
@@ -1,10 +1,11 @@
 use crate::{
-    objects::Object,
+    material::Material,
+    objects::{Object, Placement},
     precompute::Precompute,
     ray::Ray,
+    tuple::Vector,
     util::{RayTracerFloat, EPSILON},
 };
-use itertools::Itertools;
 use std::{fmt::Debug, rc::Rc};
 
 #[derive(Debug)]
@@ -31,58 +32,219 @@ impl Intersection {
     }
 
     pub fn precompute_with(self: Rc<Self>, r: &Ray, xs: Rc<Intersections>) -> Precompute {
-        let world_point = r.position(self.t);
-        let eyev = -r.direction;
-        let normalv = self.object.normal_at(world_point);
-        let inside = normalv.dot(&eyev) < 0.;
-        let over_point = world_point + normalv * EPSILON;
-        let under_point = world_point - (if inside { -normalv } else { normalv }) * EPSILON;
-        let reflectv = r.direction.reflect(&normalv);
-
-        let mut containers: Vec<Rc<Object>> = vec![];
-
-        let mut n1 = 1.0;
-        let mut n2 = 1.0;
-
-        for i in xs.as_ref().ints() {
-            let is_hit = self.as_ref() == i.as_ref();
-
-            if is_hit {
-                if containers.is_empty() {
-                    n1 = 1.0;
-                } else {
-                    n1 = containers.last().unwrap().material.refractive;
-                }
-            }
+        let upto = xs
+            .ints()
+            .iter()
+            .position(|i| i.as_ref() == self.as_ref())
+            .map_or(xs.ints().len(), |p| p + 1);
+
+        precompute(
+            Hit::new(self.t, &self.object),
+            r,
+            xs.ints()[..upto].iter().map(|i| Hit::new(i.t, &i.object)),
+            &mut vec![],
+        )
+    }
+}
+
+/// Works out everything needed to shade `hit`, given every crossing along the ray up to and
+/// including the hit itself. `containers` is scratch space.
+fn precompute<'a>(
+    hit: Hit<'a>,
+    r: &Ray,
+    crossings: impl Iterator<Item = Hit<'a>>,
+    containers: &mut Vec<(Hit<'a>, RayTracerFloat)>,
+) -> Precompute {
+    let t = hit.t;
+    let object = hit.resolve();
+    let world_point = r.position(t);
+    let eyev = -r.direction;
+    let normalv = object.normal_at(world_point);
+    let inside = normalv.dot(&eyev) < 0.;
+    let over_point = world_point + normalv * EPSILON;
+    let under_point = world_point - (if inside { -normalv } else { normalv }) * EPSILON;
 
-            if containers.contains(&i.object) {
-                containers.retain(|o| o.as_ref() != i.object.as_ref());
+    // bumps only affect shading; over/under points still follow the real surface
+    let normalv = match &object.material.normal_map {
+        Some(nm) => nm.perturb(&object, &world_point, &normalv),
+        None => normalv,
+    };
+
+    let reflectv = r.direction.reflect(&normalv);
+
+    // each container is paired with the t at which the ray entered it
+    containers.clear();
+
+    let mut n1 = 1.0;
+    let mut n2 = 1.0;
+    let mut medium = None;
+    let mut travelled = 0.;
+    let mut next_medium = None;
+
+    for i in crossings {
+        let is_hit = i.t == t;
+
+        if is_hit {
+            if let Some((o, entered)) = containers.last() {
+                n1 = o.material().refractive;
+                medium = Some(o.material().medium());
+                // only the part of the trip in front of the ray's origin counts
+                travelled = (t - entered.max(0.)) * r.direction.magnitude();
             } else {
-                containers.push(i.object.clone());
+                n1 = 1.0;
             }
+        }
 
-            if is_hit {
-                if containers.is_empty() {
-                    n2 = 1.0;
-                } else {
-                    n2 = containers.last().unwrap().material.refractive;
-                }
+        if containers.iter().any(|(o, _)| o.same_surface(&i)) {
+            containers.retain(|(o, _)| !o.same_surface(&i));
+        } else {
+            containers.push((i, i.t));
+        }
 
-                break;
+        if is_hit {
+            if let Some((o, _)) = containers.last() {
+                n2 = o.material().refractive;
+                next_medium = Some(o.material().medium());
+            } else {
+                n2 = 1.0;
             }
+
+            break;
         }
+    }
 
-        Precompute::new(
-            self.clone(),
-            world_point,
-            eyev,
-            if inside { -normalv } else { normalv },
-            reflectv,
-            inside,
-            over_point,
-            under_point,
-            n1,
-            n2,
+    Precompute::new(
+        t,
+        object,
+        world_point,
+        eyev,
+        if inside { -normalv } else { normalv },
+        reflectv,
+        inside,
+        over_point,
+        under_point,
+        n1,
+        n2,
+        medium,
+        travelled,
+        next_medium,
+    )
+}
+
+/// A crossing of a ray with an object's surface, borrowing the object rather than holding a
+/// reference count on it. Crossings with geometry reached through instances carry the
+/// instance's placement.
+#[derive(Debug, Clone, Copy)]
+pub struct Hit<'a> {
+    pub t: RayTracerFloat,
+    pub object: &'a Rc<Object>,
+    pub placement: Option<Placement<'a>>,
+}
+
+impl<'a> Hit<'a> {
+    pub fn new(t: RayTracerFloat, object: &'a Rc<Object>) -> Self {
+        Self {
+            t,
+            object,
+            placement: None,
+        }
+    }
+
+    /// The material the crossed surface is shaded with
+    pub fn material(&self) -> &'a Material {
+        self.placement
+            .and_then(|p| p.material)
+            .unwrap_or(&self.object.material)
+    }
+
+    /// The crossed object as it sits in the world. Only crossings through instances need a
+    /// new object; anything else is shared.
+    pub fn resolve(&self) -> Rc<Object> {
+        match &self.placement {
+            Some(p) => Rc::new(self.object.placed(p)),
+            None => Rc::clone(self.object),
+        }
+    }
+
+    /// Whether both crossings are with the same object in the same place. Objects are told
+    /// apart by identity, so equal but separate copies of a shape are different surfaces.
+    pub(crate) fn same_surface(&self, other: &Hit) -> bool {
+        Rc::ptr_eq(self.object, other.object) && self.placement == other.placement
+    }
+}
+
+/// Reusable storage for the crossings along a ray. It keeps its capacity between rays, so once
+/// it has grown to fit a scene, tracing rays through it allocates nothing.
+#[derive(Debug, Default)]
+pub struct IntersectionBuffer<'a> {
+    hits: Vec<Hit<'a>>,
+    containers: Vec<(Hit<'a>, RayTracerFloat)>,
+    directions: Vec<Vector>,
+}
+
+impl<'a> IntersectionBuffer<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn clear(&mut self) {
+        self.hits.clear();
+    }
+
+    pub fn push(&mut self, t: RayTracerFloat, object: &'a Rc<Object>) {
+        self.hits.push(Hit::new(t, object));
+    }
+
+    /// `push` for geometry reached through instances at `placement`
+    pub fn push_placed(
+        &mut self,
+        t: RayTracerFloat,
+        object: &'a Rc<Object>,
+        placement: Option<Placement<'a>>,
+    ) {
+        self.hits.push(Hit {
+            t,
+            object,
+            placement,
+        });
+    }
+
+    /// Orders the crossings by t, as `hits` and `hit` expect
+    pub fn sort(&mut self) {
+        self.hits.sort_unstable_by(|a, b| a.t.total_cmp(&b.t));
+    }
+
+    pub fn hits(&self) -> &[Hit<'a>] {
+        &self.hits
+    }
+
+    /// The crossings, along with scratch space for the objects the ray is inside of, each
+    /// paired with the t at which the ray entered it
+    pub(crate) fn hits_and_containers(
+        &mut self,
+    ) -> (&[Hit<'a>], &mut Vec<(Hit<'a>, RayTracerFloat)>) {
+        (&self.hits, &mut self.containers)
+    }
+
+    /// Scratch space for the directions of sampled rays. Tracing a sampled ray can sample
+    /// again, so callers push their directions above any already there and truncate back to
+    /// where they started once done.
+    pub(crate) fn directions(&mut self) -> &mut Vec<Vector> {
+        &mut self.directions
+    }
+
+    /// The index of the closest crossing in front of the ray's origin, once sorted
+    pub fn hit(&self) -> Option<usize> {
+        self.hits.iter().position(|h| h.t >= 0.)
+    }
+
+    /// `Intersection::precompute_with` for the crossing at `index`
+    pub fn precompute(&mut self, index: usize, r: &Ray) -> Precompute {
+        precompute(
+            self.hits[index],
+            r,
+            self.hits[..=index].iter().copied(),
+            &mut self.containers,
         )
     }
 }
@@ -91,11 +253,31 @@ impl Intersection {
 pub struct Intersections(Vec<Rc<Intersection>>);
 
 impl Intersections {
-    pub fn new(intersections: Vec<Rc<Intersection>>) -> Intersections {
-        let sorted = intersections
-            .iter()
-            .sorted_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
-        Intersections(sorted.cloned().collect_vec())
+    pub fn new(mut intersections: Vec<Rc<Intersection>>) -> Intersections {
+        intersections.sort_by(|a, b| a.t.partial_cmp(&b.t).unwrap());
+        Intersections(intersections)
+    }
+
+    /// The crossings in `hits` as standalone intersections. Crossings with the same surface
+    /// share one object, so they still pair up as entering and leaving it.
+    pub(crate) fn from_hits(hits: &[Hit]) -> Intersections {
+        let mut resolved: Vec<(Hit, Rc<Object>)> = vec![];
+
+        Intersections::new(
+            hits.iter()
+                .map(|h| {
+                    let object = match resolved.iter().find(|(o, _)| o.same_surface(h)) {
+                        Some((_, o)) => Rc::clone(o),
+                        None => {
+                            let o = h.resolve();
+                            resolved.push((*h, Rc::clone(&o)));
+                            o
+                        }
+                    };
+                    Rc::new(Intersection::new(h.t, object))
+                })
+                .collect(),
+        )
     }
 
     pub const fn empty() -> Intersections {
@@ -120,15 +302,21 @@ mod test {
     use approx::assert_abs_diff_eq;
 
     use crate::{
+        color::{BLACK, WHITE},
+        material::MaterialBuilder,
+        normal_map::NormalMap,
         objects::custom_glass_sphere,
         objects::default_plane,
+        objects::default_sphere,
+        objects::Object,
+        patterns::Pattern,
         ray::Ray,
-        transforms::{scaling, translation},
+        transforms::{identity, scaling, translation},
         tuple::{Point, Vector},
         util::{RayTracerFloat, EPSILON},
     };
 
-    use super::{Intersection, Intersections};
+    use super::{Intersection, IntersectionBuffer, Intersections};
 
     #[test]
     fn precompute_reflectv() {
@@ -144,6 +332,26 @@ mod test {
         assert_abs_diff_eq!(comps.reflectv, Vector::vector(0., SQRT_2 / 2., SQRT_2 / 2.));
     }
 
+    #[test]
+    fn precompute_reflectv_from_inside() {
+        let o = Rc::new(default_sphere());
+        let r = Ray::new(
+            Point::origin(),
+            Vector::vector(0., SQRT_2 / 2., SQRT_2 / 2.),
+        );
+        let i = Rc::new(Intersection::new(1., o.clone()));
+        let comps = i.clone().precompute_with(
+            &r,
+            Rc::new(Intersections::new(vec![
+                Rc::new(Intersection::new(-1., o)),
+                i,
+            ])),
+        );
+
+        assert!(comps.inside);
+        assert_abs_diff_eq!(comps.reflectv, -r.direction);
+    }
+
     #[test]
     fn precompute_n1_n2() {
         // (intersection_idx, expected_n1, expected_n2)
@@ -188,4 +396,109 @@ mod test {
         assert!(comps.under_point.z() > EPSILON / 2.);
         assert!(comps.point.z() < comps.under_point.z());
     }
+
+    #[test]
+    fn precompute_uses_bumped_normal_for_shading_only() {
+        // brightness rising along +x tilts the plane's shading normal towards -x
+        let bumps = NormalMap::pattern(Pattern::gradient(scaling(4., 1., 1.), BLACK, WHITE), 1.);
+        let o = Object::plane(
+            identity(),
+            MaterialBuilder::default().normal_map(bumps).build(),
+        );
+
+        let r = Ray::new(Point::point(1., 1., 0.), Vector::vector(0., -1., 0.));
+        let i = Rc::new(Intersection::new(1., Rc::new(o)));
+        let comps = i
+            .clone()
+            .precompute_with(&r, Rc::new(Intersections::new(vec![i])));
+
+        assert!(comps.normalv.x() < 0.);
+        assert!(comps.reflectv.x() < 0.);
+        assert_abs_diff_eq!(comps.over_point, Point::point(1., EPSILON, 0.));
+    }
+
+    #[test]
+    fn buffer_hit_is_lowest_nonnegative() {
+        let s = Rc::new(default_plane());
+        // (ts, index of the expected hit after sorting)
+        let examples: Vec<(Vec<RayTracerFloat>, Option<usize>)> = vec![
+            (vec![2., 1.], Some(0)),
+            (vec![1., -1.], Some(1)),
+            (vec![-1., -2.], None),
+            (vec![5., 7., -3., 2.], Some(1)),
+        ];
+
+        let mut buf = IntersectionBuffer::new();
+
+        for (ts, expected) in examples {
+            buf.clear();
+            ts.iter().for_each(|t| buf.push(*t, &s));
+            buf.sort();
+
+            assert_eq!(buf.hit(), expected);
+            assert!(buf.hits().windows(2).all(|w| w[0].t <= w[1].t));
+        }
+    }
+
+    #[test]
+    fn buffer_precompute_matches_intersections() {
+        let gs_a = Rc::new(custom_glass_sphere(scaling(2., 2., 2.), 1.5));
+        let gs_b = Rc::new(custom_glass_sphere(translation(0., 0., -0.25), 2.0));
+        let gs_c = Rc::new(custom_glass_sphere(translation(0., 0., 0.25), 2.5));
+
+        let r = Ray::new(Point::point(0., 0., -4.), Vector::vector(0., 0., 1.));
+
+        let mut buf = IntersectionBuffer::new();
+        for (t, o) in [
+            (6.0, &gs_a),
+            (2.0, &gs_a),
+            (4.75, &gs_b),
+            (2.75, &gs_b),
+            (3.25, &gs_c),
+            (5.25, &gs_c),
+        ] {
+            buf.push(t, o);
+        }
+        buf.sort();
+
+        let xs = Rc::new(Intersections::new(
+            buf.hits()
+                .iter()
+                .map(|h| Rc::new(Intersection::new(h.t, h.resolve())))
+                .collect(),
+        ));
+
+        for i in 0..6 {
+            let expected = xs.ints()[i].clone().precompute_with(&r, xs.clone());
+            let comps = buf.precompute(i, &r);
+
+            assert_eq!(comps.t, expected.t);
+            assert_eq!(comps.n1, expected.n1);
+            assert_eq!(comps.n2, expected.n2);
+            assert_eq!(comps.medium, expected.medium);
+            assert_eq!(comps.travelled, expected.travelled);
+        }
+    }
+
+    #[test]
+    fn equal_copies_are_separate_containers() {
+        let a = Rc::new(custom_glass_sphere(identity(), 1.5));
+        let b = Rc::new(custom_glass_sphere(identity(), 1.5));
+        let r = Ray::new(Point::point(0., 0., -4.), Vector::vector(0., 0., 1.));
+
+        let mut buf = IntersectionBuffer::new();
+        for (t, o) in [(1., &a), (2., &b), (3., &a), (4., &b)] {
+            buf.push(t, o);
+        }
+
+        // entering b while still inside a
+        let comps = buf.precompute(1, &r);
+        assert_eq!(comps.n1, 1.5);
+        assert_eq!(comps.n2, 1.5);
+
+        // crossings through an instance resolve to one object between them
+        let instance = Rc::new(Object::instance(identity(), a, None));
+        let xs = instance.intersections(&r);
+        assert!(Rc::ptr_eq(&xs.ints()[0].object, &xs.ints()[1].object));
+    }
 }
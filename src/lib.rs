@@ -1,17 +1,26 @@
-#![feature(get_mut_unchecked)]
-
+pub mod animation;
+pub mod background;
+pub mod bounds;
 pub mod camera;
 pub mod canvas;
 pub mod color;
-pub(crate) mod examples;
+pub mod error;
 pub mod intersection;
 pub mod light;
 pub mod material;
+pub mod noise;
+pub mod normal_map;
 pub mod objects;
 pub mod patterns;
 pub mod precompute;
+pub mod quaternion;
 pub mod ray;
+pub mod roots;
+pub mod sampling;
+pub mod stats;
 pub mod transforms;
 pub mod tuple;
 pub mod util;
 pub mod world;
+
+pub use error::Error;
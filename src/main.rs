@@ -1,33 +1,22 @@
-#![feature(get_mut_unchecked)]
-
 use std::f64::consts::FRAC_PI_3;
-use std::io::Result;
+use std::io::{Error, Result};
 use std::path::Path;
 
 use basic_ray_trace::basic_scene;
-use camera::Camera;
-use color::Color;
 use examples::hexagon_scene::hexagon;
-use light::PointLight;
-use tuple::{Point, Vector};
-use world::World;
+use ray_tracer_challenge_rs::{
+    camera::{self, Camera},
+    canvas,
+    color::{self, Color},
+    light::{self, PointLight},
+    material, objects, patterns, ray, transforms,
+    tuple::{self, Point, Vector},
+    util,
+    world::{self, World},
+};
 
 mod basic_ray_trace;
-mod camera;
-mod canvas;
-mod color;
 mod examples;
-mod intersection;
-mod light;
-mod material;
-mod objects;
-mod patterns;
-mod precompute;
-mod ray;
-mod transforms;
-mod tuple;
-mod util;
-mod world;
 
 fn main() -> Result<()> {
     basic_ray_trace::basic_ray_trace(Path::new("original_sphere.ppm"), transforms::identity())?;
@@ -59,5 +48,8 @@ fn main() -> Result<()> {
         Path::new("hexagon.ppm"),
     )?;
 
+    examples::turntable::hexagon_turntable(Path::new("."))
+        .map_err(|e| Error::other(e.to_string()))?;
+
     Ok(())
 }
@@ -1,17 +1,44 @@
+use std::f64::consts::PI;
+
 use crate::{
-    color::{Color, BLACK},
+    color::{Color, BLACK, WHITE},
     light::PointLight,
+    normal_map::NormalMap,
     objects::Object,
     patterns::Pattern,
     tuple::{Point, Vector},
     util::RayTracerFloat,
 };
 
-const REFRAC_VACUUM: RayTracerFloat = 1.0;
-const REFRAC_AIR: RayTracerFloat = 1.00029;
-const REFRAC_WATER: RayTracerFloat = 1.333;
-const REFRAC_GLASS: RayTracerFloat = 1.52;
-const REFRAC_DIAMOND: RayTracerFloat = 2.417;
+pub const REFRAC_VACUUM: RayTracerFloat = 1.0;
+pub const REFRAC_AIR: RayTracerFloat = 1.00029;
+pub const REFRAC_WATER: RayTracerFloat = 1.333;
+pub const REFRAC_GLASS: RayTracerFloat = 1.52;
+pub const REFRAC_DIAMOND: RayTracerFloat = 2.417;
+
+/// Cauchy B coefficients, in square micrometres, for use as `Material::dispersion`
+pub const DISPERSION_GLASS: RayTracerFloat = 0.00420;
+pub const DISPERSION_DIAMOND: RayTracerFloat = 0.0125;
+
+/// Wavelength, in micrometres, at which `Material::refractive` is measured (the sodium D line)
+pub const REFERENCE_WAVELENGTH: RayTracerFloat = 0.5893;
+
+/// Wavelengths, in micrometres, standing in for the red, green and blue channels
+pub const CHANNEL_WAVELENGTHS: [RayTracerFloat; 3] = [0.65, 0.532, 0.45];
+
+/// How direct light is reflected off a surface
+#[derive(Debug, Default, PartialEq, Copy, Clone)]
+pub enum ShadingModel {
+    /// classic Phong, using `diffuse`, `specular` and `shininess`
+    #[default]
+    Phong,
+    /// Cook-Torrance microfacets with a GGX distribution; the pattern supplies the base color.
+    /// `metallic` and `roughness` run from 0 to 1.
+    CookTorrance {
+        metallic: RayTracerFloat,
+        roughness: RayTracerFloat,
+    },
+}
 
 pub struct MaterialBuilder {
     pattern: Pattern,
@@ -22,6 +49,14 @@ pub struct MaterialBuilder {
     reflective: RayTracerFloat,
     transparency: RayTracerFloat,
     refractive: RayTracerFloat,
+    normal_map: Option<NormalMap>,
+    shading: ShadingModel,
+    roughness: RayTracerFloat,
+    roughness_samples: usize,
+    absorption: Color,
+    dispersion: RayTracerFloat,
+    casts_shadow: bool,
+    receives_shadow: bool,
 }
 
 impl Default for MaterialBuilder {
@@ -35,6 +70,14 @@ impl Default for MaterialBuilder {
             reflective: 0.,
             transparency: 0.,
             refractive: 1.,
+            normal_map: None,
+            shading: ShadingModel::Phong,
+            roughness: 0.,
+            roughness_samples: 16,
+            absorption: BLACK,
+            dispersion: 0.,
+            casts_shadow: true,
+            receives_shadow: true,
         }
     }
 }
@@ -85,6 +128,54 @@ impl MaterialBuilder {
         self
     }
 
+    pub fn normal_map(mut self, nm: NormalMap) -> Self {
+        self.normal_map = Some(nm);
+        self
+    }
+
+    pub fn roughness(mut self, ro: RayTracerFloat) -> Self {
+        self.roughness = ro;
+        self
+    }
+
+    pub fn roughness_samples(mut self, rs: usize) -> Self {
+        self.roughness_samples = rs;
+        self
+    }
+
+    pub fn absorption(mut self, ab: Color) -> Self {
+        self.absorption = ab;
+        self
+    }
+
+    pub fn dispersion(mut self, di: RayTracerFloat) -> Self {
+        self.dispersion = di;
+        self
+    }
+
+    pub fn casts_shadow(mut self, cs: bool) -> Self {
+        self.casts_shadow = cs;
+        self
+    }
+
+    pub fn receives_shadow(mut self, rs: bool) -> Self {
+        self.receives_shadow = rs;
+        self
+    }
+
+    pub fn shading(mut self, sm: ShadingModel) -> Self {
+        self.shading = sm;
+        self
+    }
+
+    /// Shorthand for Cook-Torrance shading with the given parameters
+    pub fn pbr(self, metallic: RayTracerFloat, roughness: RayTracerFloat) -> Self {
+        self.shading(ShadingModel::CookTorrance {
+            metallic,
+            roughness,
+        })
+    }
+
     pub fn build(self) -> Material {
         Material {
             pattern: self.pattern,
@@ -95,11 +186,19 @@ impl MaterialBuilder {
             reflective: self.reflective,
             transparency: self.transparency,
             refractive: self.refractive,
+            normal_map: self.normal_map,
+            shading: self.shading,
+            roughness: self.roughness,
+            roughness_samples: self.roughness_samples,
+            absorption: self.absorption,
+            dispersion: self.dispersion,
+            casts_shadow: self.casts_shadow,
+            receives_shadow: self.receives_shadow,
         }
     }
 }
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Material {
     pub pattern: Pattern,
     pub ambient: RayTracerFloat,
@@ -109,6 +208,52 @@ pub struct Material {
     pub reflective: RayTracerFloat,
     pub transparency: RayTracerFloat,
     pub refractive: RayTracerFloat,
+    /// perturbs shading normals; None shades with the geometric normal
+    pub normal_map: Option<NormalMap>,
+    pub shading: ShadingModel,
+    /// spreads reflected and refracted rays over a cone, from 0 (perfectly sharp) to 1 (a full
+    /// hemisphere); independent of the Cook-Torrance roughness, which only shapes highlights
+    pub roughness: RayTracerFloat,
+    /// rays traced per reflection or refraction when `roughness` is non-zero
+    pub roughness_samples: usize,
+    /// fraction of each channel absorbed per unit of distance travelled through the object's
+    /// interior; black is perfectly clear
+    pub absorption: Color,
+    /// Cauchy B coefficient, in square micrometres: how much more strongly shorter wavelengths
+    /// are bent than `refractive` implies; 0 bends every wavelength alike
+    pub dispersion: RayTracerFloat,
+    /// whether the object blocks light on its way to other objects
+    pub casts_shadow: bool,
+    /// whether shadows are drawn on the object; if not it is lit as though nothing blocks the
+    /// light
+    pub receives_shadow: bool,
+}
+
+/// The parts of a material that affect light travelling through its interior
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub struct Medium {
+    pub refractive: RayTracerFloat,
+    pub dispersion: RayTracerFloat,
+    pub absorption: Color,
+}
+
+impl Medium {
+    /// The fraction of each channel surviving `distance` units inside the medium, following the
+    /// Beer-Lambert law
+    pub fn transmittance(&self, distance: RayTracerFloat) -> Color {
+        let a = self.absorption;
+        Color::new(
+            (-a.red() * distance).exp(),
+            (-a.green() * distance).exp(),
+            (-a.blue() * distance).exp(),
+        )
+    }
+
+    /// The refractive index for light of `wavelength` micrometres, following Cauchy's equation
+    /// anchored so that the reference wavelength sees exactly `refractive`
+    pub fn refractive_at(&self, wavelength: RayTracerFloat) -> RayTracerFloat {
+        self.refractive + self.dispersion * (wavelength.powi(-2) - REFERENCE_WAVELENGTH.powi(-2))
+    }
 }
 
 impl Default for Material {
@@ -120,7 +265,7 @@ impl Default for Material {
 impl Material {
     pub fn from(other: &Material) -> Self {
         Self {
-            pattern: other.pattern,
+            pattern: other.pattern.clone(),
             ambient: other.ambient,
             diffuse: other.diffuse,
             specular: other.specular,
@@ -128,9 +273,34 @@ impl Material {
             reflective: other.reflective,
             transparency: other.transparency,
             refractive: other.refractive,
+            normal_map: other.normal_map.clone(),
+            shading: other.shading,
+            roughness: other.roughness,
+            roughness_samples: other.roughness_samples,
+            absorption: other.absorption,
+            dispersion: other.dispersion,
+            casts_shadow: other.casts_shadow,
+            receives_shadow: other.receives_shadow,
         }
     }
 
+    /// What a ray passing through an object made of this material sees of it
+    pub fn medium(&self) -> Medium {
+        Medium {
+            refractive: self.refractive,
+            dispersion: self.dispersion,
+            absorption: self.absorption,
+        }
+    }
+
+    pub fn transmittance(&self, distance: RayTracerFloat) -> Color {
+        self.medium().transmittance(distance)
+    }
+
+    pub fn refractive_at(&self, wavelength: RayTracerFloat) -> RayTracerFloat {
+        self.medium().refractive_at(wavelength)
+    }
+
     pub fn lighting(
         &self,
         object: &Object,
@@ -140,6 +310,38 @@ impl Material {
         normalv: Vector,
         in_shadow: bool,
     ) -> Color {
+        let transmission = if in_shadow { BLACK } else { WHITE };
+        self.lighting_through(object, light, point, eyev, normalv, transmission)
+    }
+
+    /// Like `lighting`, but with `transmission` giving the fraction of each channel of the
+    /// light that reaches the point past any shadowing objects
+    pub fn lighting_through(
+        &self,
+        object: &Object,
+        light: PointLight,
+        point: Point,
+        eyev: Vector,
+        normalv: Vector,
+        transmission: Color,
+    ) -> Color {
+        if let ShadingModel::CookTorrance {
+            metallic,
+            roughness,
+        } = self.shading
+        {
+            return self.cook_torrance(
+                self.pattern.color_at(object, &point),
+                metallic,
+                roughness,
+                light,
+                point,
+                eyev,
+                normalv,
+                transmission,
+            );
+        }
+
         // combine the surface color with the light's color/intensity
         let effective_color = self.pattern.color_at(object, &point) * light.intensity;
 
@@ -154,11 +356,11 @@ impl Material {
         // light is on the other side of the surface.
         let light_dot_normal = lightv.dot(&normalv);
 
-        let (diffuse, specular) = if light_dot_normal < 0. || in_shadow {
+        let (diffuse, specular) = if light_dot_normal < 0. || transmission == BLACK {
             (BLACK, BLACK)
         } else {
             // compute the diffuse contribution
-            let diffuse = effective_color * self.diffuse * light_dot_normal;
+            let diffuse = effective_color * transmission * self.diffuse * light_dot_normal;
 
             // ceflect_dot_eye represents the cosine of the angle between the
             // reflection vector and the eye vector. A negative number means the
@@ -171,7 +373,7 @@ impl Material {
             } else {
                 // compute the specular contribution
                 let factor = reflect_dot_eye.powf(self.shininess);
-                light.intensity * self.specular * factor
+                light.intensity * transmission * self.specular * factor
             };
 
             (diffuse, specular)
@@ -180,11 +382,107 @@ impl Material {
         // # Add the three contributions together to get the final shading
         ambient + diffuse + specular
     }
+
+    /// Cook-Torrance with GGX distribution, Smith-Schlick geometry and Schlick Fresnel terms.
+    /// The light's intensity is taken as irradiance on a surface facing it, which makes a rough
+    /// white dielectric about as bright as the Phong default.
+    #[allow(clippy::too_many_arguments)]
+    fn cook_torrance(
+        &self,
+        base_color: Color,
+        metallic: RayTracerFloat,
+        roughness: RayTracerFloat,
+        light: PointLight,
+        point: Point,
+        eyev: Vector,
+        normalv: Vector,
+        transmission: Color,
+    ) -> Color {
+        let metallic = metallic.clamp(0., 1.);
+        // a perfectly smooth surface would reflect a point light from a single point only
+        let roughness = roughness.clamp(0.02, 1.);
+
+        let ambient = base_color * light.intensity * self.ambient;
+
+        let lightv = (light.position - point).normalize();
+        let n_dot_l = normalv.dot(&lightv);
+        let n_dot_v = normalv.dot(&eyev);
+
+        if n_dot_l <= 0. || n_dot_v <= 0. || transmission == BLACK {
+            return ambient;
+        }
+
+        let halfv = (lightv + eyev).normalize();
+        let n_dot_h = normalv.dot(&halfv).max(0.);
+        let v_dot_h = eyev.dot(&halfv).max(0.);
+
+        // normal distribution
+        let alpha2 = roughness.powi(4);
+        let d = alpha2 / (PI * (n_dot_h.powi(2) * (alpha2 - 1.) + 1.).powi(2));
+
+        // geometric shadowing/masking
+        let k = (roughness + 1.).powi(2) / 8.;
+        let g1 = |x: RayTracerFloat| x / (x * (1. - k) + k);
+        let g = g1(n_dot_l) * g1(n_dot_v);
+
+        // metals tint their reflections; dielectrics reflect about 4% head on
+        let f0 = Color::new(0.04, 0.04, 0.04) * (1. - metallic) + base_color * metallic;
+        let fresnel = f0 + (WHITE - f0) * (1. - v_dot_h).powi(5);
+
+        let specular = fresnel * (d * g / (4. * n_dot_l * n_dot_v));
+        let diffuse = (WHITE - fresnel) * base_color * ((1. - metallic) / PI);
+
+        // π converts the irradiance to the outgoing radiance scale
+        ambient + (diffuse + specular) * light.intensity * transmission * (PI * n_dot_l)
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::MaterialBuilder;
+    use approx::assert_abs_diff_eq;
+
+    use crate::{
+        color::{Color, WHITE},
+        light::PointLight,
+        objects::default_sphere,
+        tuple::{Point, Vector},
+    };
+
+    use super::{
+        Material, MaterialBuilder, CHANNEL_WAVELENGTHS, DISPERSION_DIAMOND, REFERENCE_WAVELENGTH,
+        REFRAC_DIAMOND,
+    };
+
+    fn head_on(m: &Material, eyev: Vector, in_shadow: bool) -> Color {
+        let light = PointLight::new(Point::point(0., 0., -10.), WHITE);
+        m.lighting(
+            &default_sphere(),
+            light,
+            Point::point(0., 0., 0.),
+            eyev,
+            Vector::vector(0., 0., -1.),
+            in_shadow,
+        )
+    }
+
+    #[test]
+    fn cauchy_dispersion() {
+        let m = MaterialBuilder::default()
+            .refractive(REFRAC_DIAMOND)
+            .dispersion(DISPERSION_DIAMOND)
+            .build();
+
+        assert_abs_diff_eq!(m.refractive_at(REFERENCE_WAVELENGTH), REFRAC_DIAMOND);
+
+        let [red, green, blue] = CHANNEL_WAVELENGTHS.map(|w| m.refractive_at(w));
+        assert!(red < green && green < blue);
+
+        let plain = Material::default();
+        assert_eq!(
+            plain.refractive_at(CHANNEL_WAVELENGTHS[2]),
+            plain.refractive
+        );
+    }
 
     #[test]
     fn material_defaults() {
@@ -193,4 +491,52 @@ mod test {
         assert_eq!(m.transparency, 0.);
         assert_eq!(m.refractive, 1.);
     }
+
+    #[test]
+    fn rough_dielectric_is_mostly_diffuse() {
+        let m = MaterialBuilder::default().ambient(0.).pbr(0., 1.).build();
+        let c = head_on(&m, Vector::vector(0., 0., -1.), false);
+
+        // close to a Lambertian white surface, plus a faint specular sheen
+        assert!(c.red() > 0.9 && c.red() < 1.1);
+        assert_abs_diff_eq!(c.red(), c.blue());
+    }
+
+    #[test]
+    fn metals_tint_highlights_and_have_no_diffuse() {
+        let gold = Color::new(1., 0.78, 0.34);
+        let m = MaterialBuilder::default()
+            .color(gold)
+            .ambient(0.)
+            .pbr(1., 0.3)
+            .build();
+
+        let highlight = head_on(&m, Vector::vector(0., 0., -1.), false);
+        assert!(highlight.red() > highlight.green() && highlight.green() > highlight.blue());
+
+        // well away from the mirror direction a smooth metal is nearly black
+        let off_angle = head_on(&m, Vector::vector(0., 0.8, -0.6), false);
+        assert!(off_angle.red() < 0.05 * highlight.red());
+    }
+
+    #[test]
+    fn smoother_surfaces_have_sharper_highlights() {
+        let smooth = MaterialBuilder::default().ambient(0.).pbr(0., 0.2).build();
+        let rough = MaterialBuilder::default().ambient(0.).pbr(0., 0.8).build();
+        let eyev = Vector::vector(0., 0., -1.);
+
+        assert!(head_on(&smooth, eyev, false).red() > head_on(&rough, eyev, false).red());
+    }
+
+    #[test]
+    fn pbr_in_shadow_is_ambient_only() {
+        let m = MaterialBuilder::default()
+            .ambient(0.25)
+            .pbr(0.5, 0.5)
+            .build();
+        assert_abs_diff_eq!(
+            head_on(&m, Vector::vector(0., 0., -1.), true),
+            Color::new(0.25, 0.25, 0.25)
+        );
+    }
 }
@@ -0,0 +1,220 @@
+use crate::{
+    tuple::{Point, Vector},
+    util::RayTracerFloat,
+};
+
+/// Ken Perlin's reference permutation of 0..=255
+#[rustfmt::skip]
+const PERMUTATION: [u8; 256] = [
+    151, 160, 137, 91, 90, 15, 131, 13, 201, 95, 96, 53, 194, 233, 7, 225,
+    140, 36, 103, 30, 69, 142, 8, 99, 37, 240, 21, 10, 23, 190, 6, 148,
+    247, 120, 234, 75, 0, 26, 197, 62, 94, 252, 219, 203, 117, 35, 11, 32,
+    57, 177, 33, 88, 237, 149, 56, 87, 174, 20, 125, 136, 171, 168, 68, 175,
+    74, 165, 71, 134, 139, 48, 27, 166, 77, 146, 158, 231, 83, 111, 229, 122,
+    60, 211, 133, 230, 220, 105, 92, 41, 55, 46, 245, 40, 244, 102, 143, 54,
+    65, 25, 63, 161, 1, 216, 80, 73, 209, 76, 132, 187, 208, 89, 18, 169,
+    200, 196, 135, 130, 116, 188, 159, 86, 164, 100, 109, 198, 173, 186, 3, 64,
+    52, 217, 226, 250, 124, 123, 5, 202, 38, 147, 118, 126, 255, 82, 85, 212,
+    207, 206, 59, 227, 47, 16, 58, 17, 182, 189, 28, 42, 223, 183, 170, 213,
+    119, 248, 152, 2, 44, 154, 163, 70, 221, 153, 101, 155, 167, 43, 172, 9,
+    129, 22, 39, 253, 19, 98, 108, 110, 79, 113, 224, 232, 178, 185, 112, 104,
+    218, 246, 97, 228, 251, 34, 242, 193, 238, 210, 144, 12, 191, 179, 162, 241,
+    81, 51, 145, 235, 249, 14, 239, 107, 49, 192, 214, 31, 181, 199, 106, 157,
+    184, 84, 204, 176, 115, 121, 50, 45, 127, 4, 150, 254, 138, 236, 205, 93,
+    222, 114, 67, 29, 24, 72, 243, 141, 128, 195, 78, 66, 215, 61, 156, 180,
+];
+
+fn hash(i: usize) -> usize {
+    PERMUTATION[i & 255] as usize
+}
+
+fn fade(t: RayTracerFloat) -> RayTracerFloat {
+    t * t * t * (t * (t * 6. - 15.) + 10.)
+}
+
+fn lerp(t: RayTracerFloat, a: RayTracerFloat, b: RayTracerFloat) -> RayTracerFloat {
+    a + t * (b - a)
+}
+
+/// Dot product of (x, y, z) with one of 12 gradient directions picked by `hash`
+fn grad(hash: usize, x: RayTracerFloat, y: RayTracerFloat, z: RayTracerFloat) -> RayTracerFloat {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = match h {
+        _ if h < 4 => y,
+        12 | 14 => x,
+        _ => z,
+    };
+
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+/// Improved Perlin gradient noise: smooth, roughly in [-1, 1], zero at every integer lattice
+/// point and repeating every 256 units
+pub fn noise(p: &Point) -> RayTracerFloat {
+    let cell = |c: RayTracerFloat| (c.floor() as i64).rem_euclid(256) as usize;
+    let (xi, yi, zi) = (cell(p.x()), cell(p.y()), cell(p.z()));
+
+    let x = p.x() - p.x().floor();
+    let y = p.y() - p.y().floor();
+    let z = p.z() - p.z().floor();
+
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+
+    let a = hash(xi) + yi;
+    let aa = hash(a) + zi;
+    let ab = hash(a + 1) + zi;
+    let b = hash(xi + 1) + yi;
+    let ba = hash(b) + zi;
+    let bb = hash(b + 1) + zi;
+
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, grad(hash(aa), x, y, z), grad(hash(ba), x - 1., y, z)),
+            lerp(
+                u,
+                grad(hash(ab), x, y - 1., z),
+                grad(hash(bb), x - 1., y - 1., z),
+            ),
+        ),
+        lerp(
+            v,
+            lerp(
+                u,
+                grad(hash(aa + 1), x, y, z - 1.),
+                grad(hash(ba + 1), x - 1., y, z - 1.),
+            ),
+            lerp(
+                u,
+                grad(hash(ab + 1), x, y - 1., z - 1.),
+                grad(hash(bb + 1), x - 1., y - 1., z - 1.),
+            ),
+        ),
+    )
+}
+
+/// How octaves of noise are summed
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum Fractal {
+    /// a single octave
+    Plain,
+    /// fractal Brownian motion: each octave doubles the frequency and halves the amplitude
+    Fbm { octaves: u32 },
+    /// like fBm but summing absolute values, giving sharp creases
+    Turbulence { octaves: u32 },
+}
+
+impl Fractal {
+    /// Fractal noise at `p`, normalized so it stays roughly within [-1, 1] (or [0, 1] for
+    /// turbulence) regardless of the number of octaves
+    pub fn sample(&self, p: &Point) -> RayTracerFloat {
+        match self {
+            Fractal::Plain => noise(p),
+            Fractal::Fbm { octaves } => Self::octaves(p, *octaves, noise),
+            Fractal::Turbulence { octaves } => Self::octaves(p, *octaves, |p| noise(p).abs()),
+        }
+    }
+
+    fn octaves(p: &Point, octaves: u32, f: impl Fn(&Point) -> RayTracerFloat) -> RayTracerFloat {
+        let mut total = 0.;
+        let mut max = 0.;
+        let mut frequency = 1.;
+        let mut amplitude = 1.;
+
+        for _ in 0..octaves.max(1) {
+            total += f(&Point::point(
+                p.x() * frequency,
+                p.y() * frequency,
+                p.z() * frequency,
+            )) * amplitude;
+            max += amplitude;
+            frequency *= 2.;
+            amplitude *= 0.5;
+        }
+
+        total / max
+    }
+
+    /// A noise-driven offset for `p`, with each component sampled from an unrelated region of
+    /// the noise field
+    pub fn displacement(&self, p: &Point) -> Vector {
+        let offset = |dx, dy, dz| Point::point(p.x() + dx, p.y() + dy, p.z() + dz);
+
+        Vector::vector(
+            self.sample(p),
+            self.sample(&offset(31.7, 47.3, 12.9)),
+            self.sample(&offset(-53.1, 17.9, 71.3)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+
+    use crate::tuple::Point;
+
+    use super::{noise, Fractal, PERMUTATION};
+
+    fn sample_points() -> impl Iterator<Item = Point> {
+        (0..500).map(|i| {
+            let i = i as f64;
+            Point::point(i * 0.137 - 20., i * 0.071 + 3.3, i * -0.213 + 7.)
+        })
+    }
+
+    #[test]
+    fn permutation_is_complete() {
+        assert_eq!(PERMUTATION.iter().collect::<HashSet<_>>().len(), 256);
+    }
+
+    #[test]
+    fn noise_vanishes_on_lattice() {
+        for (x, y, z) in [(0., 0., 0.), (1., 2., 3.), (-4., 7., -11.), (300., -2., 5.)] {
+            assert_eq!(noise(&Point::point(x, y, z)), 0.);
+        }
+    }
+
+    #[test]
+    fn noise_is_bounded_and_varied() {
+        let samples: Vec<_> = sample_points().map(|p| noise(&p)).collect();
+
+        assert!(samples.iter().all(|n| (-1. ..=1.).contains(n)));
+        assert!(samples.iter().any(|n| *n > 0.2));
+        assert!(samples.iter().any(|n| *n < -0.2));
+    }
+
+    #[test]
+    fn noise_is_continuous() {
+        for p in sample_points() {
+            let nearby = Point::point(p.x() + 1e-6, p.y() - 1e-6, p.z() + 1e-6);
+            assert!((noise(&p) - noise(&nearby)).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn noise_repeats_every_256_units() {
+        for p in sample_points().take(20) {
+            let shifted = Point::point(p.x() + 256., p.y(), p.z() - 256.);
+            assert!((noise(&p) - noise(&shifted)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn fractal_ranges() {
+        let fbm = Fractal::Fbm { octaves: 6 };
+        let turbulence = Fractal::Turbulence { octaves: 6 };
+
+        for p in sample_points() {
+            assert!((-1. ..=1.).contains(&fbm.sample(&p)));
+            assert!((0. ..=1.).contains(&turbulence.sample(&p)));
+        }
+
+        assert_eq!(
+            Fractal::Fbm { octaves: 1 }.sample(&Point::point(0.3, 0.2, 0.1)),
+            Fractal::Plain.sample(&Point::point(0.3, 0.2, 0.1))
+        );
+    }
+}
@@ -0,0 +1,356 @@
+use crate::{
+    error::Result,
+    noise::Fractal,
+    objects::Object,
+    patterns::{texture::Texture, uv::UvMapping, Pattern},
+    transforms::{InvertibleTransform, Transform},
+    tuple::{Point, Vector},
+    util::{RayTracerFloat, EPSILON},
+};
+
+/// Step used for the finite differences that estimate height and texture-coordinate gradients
+const DELTA: RayTracerFloat = 1e-4;
+
+/// A way of perturbing an object's shading normal without changing its geometry
+#[derive(Debug, PartialEq, Clone)]
+pub enum NormalMap {
+    /// bumps following a noise height field sampled in object space
+    Noise {
+        transform: InvertibleTransform,
+        fractal: Fractal,
+        depth: RayTracerFloat,
+    },
+    /// bumps following a pattern, with brighter regions standing higher
+    Pattern {
+        pattern: Pattern,
+        depth: RayTracerFloat,
+    },
+    /// a tangent-space normal map image: red runs along increasing u, green along increasing v
+    /// and blue out of the surface
+    Image {
+        transform: InvertibleTransform,
+        mapping: UvMapping,
+        texture: Texture,
+    },
+}
+
+impl NormalMap {
+    pub fn noise(transform: Transform, fractal: Fractal, depth: RayTracerFloat) -> Self {
+        Self::try_noise(transform, fractal, depth)
+            .unwrap_or_else(|e| panic!("cannot create normal map: {}", e))
+    }
+
+    pub fn try_noise(
+        transform: Transform,
+        fractal: Fractal,
+        depth: RayTracerFloat,
+    ) -> Result<Self> {
+        Ok(NormalMap::Noise {
+            transform: InvertibleTransform::new(transform)?,
+            fractal,
+            depth,
+        })
+    }
+
+    pub fn pattern(pattern: Pattern, depth: RayTracerFloat) -> Self {
+        NormalMap::Pattern { pattern, depth }
+    }
+
+    pub fn image(transform: Transform, mapping: UvMapping, texture: Texture) -> Self {
+        Self::try_image(transform, mapping, texture)
+            .unwrap_or_else(|e| panic!("cannot create normal map: {}", e))
+    }
+
+    pub fn try_image(transform: Transform, mapping: UvMapping, texture: Texture) -> Result<Self> {
+        Ok(NormalMap::Image {
+            transform: InvertibleTransform::new(transform)?,
+            mapping,
+            texture,
+        })
+    }
+
+    /// Perturbs `normal`, the normalized geometric normal of `object` at `world_point`
+    pub fn perturb(&self, object: &Object, world_point: &Point, normal: &Vector) -> Vector {
+        match self {
+            NormalMap::Noise {
+                transform,
+                fractal,
+                depth,
+            } => {
+                let height = |p: &Point| {
+                    fractal.sample(
+                        &object
+                            .world_point_to_local(*p)
+                            .transform(transform.inverse()),
+                    )
+                };
+
+                bump(normal, gradient(world_point, height), *depth)
+            }
+            NormalMap::Pattern { pattern, depth } => {
+                let height = |p: &Point| {
+                    let c = pattern.color_at(object, p);
+                    (c.red() + c.green() + c.blue()) / 3.
+                };
+
+                bump(normal, gradient(world_point, height), *depth)
+            }
+            NormalMap::Image {
+                transform,
+                mapping,
+                texture,
+            } => {
+                let uv = |p: &Point| {
+                    mapping.map(
+                        &object
+                            .world_point_to_local(*p)
+                            .transform(transform.inverse()),
+                    )
+                };
+
+                let (u, v) = uv(world_point);
+                let c = texture.color_at(u, v);
+
+                let (tangent, bitangent) = tangent_frame(
+                    normal,
+                    along_surface(normal, wrapped_gradient(world_point, |p| uv(p).0)),
+                    along_surface(normal, wrapped_gradient(world_point, |p| uv(p).1)),
+                );
+
+                (tangent * (c.red() * 2. - 1.)
+                    + bitangent * (c.green() * 2. - 1.)
+                    + *normal * (c.blue() * 2. - 1.))
+                    .normalize()
+            }
+        }
+    }
+}
+
+/// Central-difference gradient of `f` at `p`
+fn gradient(p: &Point, f: impl Fn(&Point) -> RayTracerFloat) -> Vector {
+    difference(p, f, |d| d)
+}
+
+/// `gradient` for texture coordinates, which repeat every unit: jumps bigger than half a unit
+/// are treated as wrapping around, so texture seams don't produce huge spikes
+fn wrapped_gradient(p: &Point, f: impl Fn(&Point) -> RayTracerFloat) -> Vector {
+    difference(p, f, |d| d - d.round())
+}
+
+fn difference(
+    p: &Point,
+    f: impl Fn(&Point) -> RayTracerFloat,
+    adjust: impl Fn(RayTracerFloat) -> RayTracerFloat,
+) -> Vector {
+    let diff = |dx, dy, dz| {
+        let ahead = f(&Point::point(p.x() + dx, p.y() + dy, p.z() + dz));
+        let behind = f(&Point::point(p.x() - dx, p.y() - dy, p.z() - dz));
+        adjust(ahead - behind) / (2. * DELTA)
+    };
+
+    Vector::vector(
+        diff(DELTA, 0., 0.),
+        diff(0., DELTA, 0.),
+        diff(0., 0., DELTA),
+    )
+}
+
+/// The part of `v` lying in the plane perpendicular to `normal`
+fn along_surface(normal: &Vector, v: Vector) -> Vector {
+    let v = v - *normal * v.dot(normal);
+
+    if v.magnitude() == 0. {
+        v
+    } else {
+        v.normalize()
+    }
+}
+
+/// An orthonormal frame in the plane perpendicular to `normal`, following `tangent` and
+/// `bitangent` where the mapping gives them and made up where it doesn't, as at a sphere's poles
+fn tangent_frame(normal: &Vector, tangent: Vector, bitangent: Vector) -> (Vector, Vector) {
+    // keep the frame orthogonal even where the mapping is sheared
+    let bitangent = bitangent - tangent * bitangent.dot(&tangent);
+    let usable = |v: &Vector| v.magnitude() > EPSILON;
+
+    match (usable(&tangent), usable(&bitangent)) {
+        (true, true) => (tangent, bitangent.normalize()),
+        (true, false) => (tangent, tangent.cross(normal)),
+        (false, true) => {
+            let bitangent = bitangent.normalize();
+            (normal.cross(&bitangent), bitangent)
+        }
+        (false, false) => {
+            // any vector not parallel to the normal will do
+            let helper = if normal.x().abs() < 0.9 {
+                Vector::vector(1., 0., 0.)
+            } else {
+                Vector::vector(0., 1., 0.)
+            };
+            let tangent = along_surface(normal, helper);
+            (tangent, tangent.cross(normal))
+        }
+    }
+}
+
+/// Tilts `normal` away from uphill, by `depth` times the slope of the height field
+fn bump(normal: &Vector, height_gradient: Vector, depth: RayTracerFloat) -> Vector {
+    let slope = height_gradient - *normal * height_gradient.dot(normal);
+    (*normal - slope * depth).normalize()
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use approx::assert_abs_diff_eq;
+
+    use crate::{
+        canvas::Canvas,
+        color::Color,
+        error::Error,
+        noise::Fractal,
+        objects::{default_plane, default_sphere},
+        patterns::{
+            texture::{Filter, Texture, Wrap},
+            uv::UvMapping,
+            Pattern,
+        },
+        transforms::{identity, scaling, translation},
+        tuple::{Point, Vector},
+    };
+
+    use super::NormalMap;
+
+    fn one_pixel(c: Color) -> Texture {
+        let mut image = Canvas::new(1, 1);
+        image.write(0, 0, c);
+        Texture::new(Rc::new(image), Filter::Nearest, Wrap::Repeat).unwrap()
+    }
+
+    #[test]
+    fn flat_height_field_leaves_normal_alone() {
+        let map = NormalMap::pattern(Pattern::Solid(Color::new(0.5, 0.5, 0.5)), 1.);
+        let n = Vector::vector(0., 1., 0.);
+
+        assert_abs_diff_eq!(
+            map.perturb(&default_plane(), &Point::point(0.3, 0., 0.7), &n),
+            n
+        );
+    }
+
+    #[test]
+    fn gradient_pattern_tilts_away_from_uphill() {
+        // brightness rises along +x, so the normal should lean towards -x
+        let map = NormalMap::pattern(
+            Pattern::gradient(
+                scaling(10., 1., 1.),
+                Color::new(0., 0., 0.),
+                Color::new(1., 1., 1.),
+            ),
+            1.,
+        );
+        let n = Vector::vector(0., 1., 0.);
+        let bumped = map.perturb(&default_plane(), &Point::point(2.5, 0., 0.), &n);
+
+        assert!(bumped.x() < -0.05);
+        assert_abs_diff_eq!(bumped.z(), 0.);
+        assert_abs_diff_eq!(bumped.magnitude(), 1.);
+    }
+
+    #[test]
+    fn noise_bumps_vary_over_surface() {
+        let map = NormalMap::noise(scaling(0.2, 0.2, 0.2), Fractal::Fbm { octaves: 3 }, 0.3);
+        let n = Vector::vector(0., 1., 0.);
+
+        let normals: Vec<_> = (0..10)
+            .map(|i| {
+                map.perturb(
+                    &default_plane(),
+                    &Point::point(i as f64 * 0.37, 0., 0.1),
+                    &n,
+                )
+            })
+            .collect();
+
+        assert!(normals.iter().all(|b| b.dot(&n) > 0.));
+        assert!(normals.windows(2).any(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn height_steps_are_not_wrapped() {
+        // a step of 0.6 in height, and one of a whole unit, must still tilt towards -x
+        for (low, high) in [(0.2, 0.8), (0., 1.)] {
+            let map = NormalMap::pattern(
+                Pattern::stripe(
+                    translation(0.5, 0., 0.),
+                    Color::new(high, high, high),
+                    Color::new(low, low, low),
+                ),
+                1.,
+            );
+            let n = Vector::vector(0., 1., 0.);
+            let bumped = map.perturb(&default_plane(), &Point::point(0.5, 0., 0.), &n);
+
+            assert!(bumped.x() < -0.5, "{:?}", bumped);
+        }
+    }
+
+    #[test]
+    fn normal_map_at_a_pole_stays_finite() {
+        let map = NormalMap::image(
+            identity(),
+            UvMapping::Spherical,
+            one_pixel(Color::new(1., 0.5, 0.5)),
+        );
+
+        let n = Vector::vector(0., 1., 0.);
+        let bumped = map.perturb(&default_sphere(), &Point::point(0., 1., 0.), &n);
+
+        assert_abs_diff_eq!(bumped.magnitude(), 1.);
+        assert_abs_diff_eq!(bumped.dot(&n), 0.);
+    }
+
+    #[test]
+    fn flat_normal_map_keeps_normal() {
+        let map = NormalMap::image(
+            identity(),
+            UvMapping::Spherical,
+            one_pixel(Color::new(0.5, 0.5, 1.)),
+        );
+
+        let p = Point::point(0., 0., -1.);
+        let n = Vector::vector(0., 0., -1.);
+
+        assert_abs_diff_eq!(map.perturb(&default_sphere(), &p, &n), n);
+    }
+
+    #[test]
+    fn normal_map_leans_along_tangent() {
+        // a pure +u normal lies along the direction u increases
+        let map = NormalMap::image(
+            identity(),
+            UvMapping::Planar,
+            one_pixel(Color::new(1., 0.5, 0.5)),
+        );
+
+        let n = Vector::vector(0., 1., 0.);
+        let bumped = map.perturb(&default_plane(), &Point::point(0.3, 0., 0.6), &n);
+
+        assert_abs_diff_eq!(bumped, Vector::vector(1., 0., 0.));
+    }
+
+    #[test]
+    fn try_constructors_reject_singular_transforms() {
+        let flat = scaling(1., 0., 1.);
+
+        assert_eq!(
+            NormalMap::try_noise(flat, Fractal::Fbm { octaves: 2 }, 0.3),
+            Err(Error::NonInvertibleTransform)
+        );
+        assert_eq!(
+            NormalMap::try_image(flat, UvMapping::Planar, one_pixel(Color::new(0.5, 0.5, 1.))),
+            Err(Error::NonInvertibleTransform)
+        );
+    }
+}
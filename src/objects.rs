@@ -1,18 +1,32 @@
-use crate::intersection::{Intersection, Intersections};
+use crate::bounds::Bounds;
+use crate::error::{Error, Result};
+use crate::intersection::{IntersectionBuffer, Intersections};
 use crate::material::{Material, MaterialBuilder};
 use crate::ray::Ray;
-use crate::transforms::{identity, Transform};
+use crate::roots::quartic;
+use crate::stats;
+use crate::transforms::{identity, InvertibleTransform, Transform};
 use crate::tuple::{Point, Tuple, Vector};
 use crate::util::{RayTracerFloat, EPSILON};
+use std::f64::consts::TAU;
 use std::fmt::Debug;
 use std::mem::swap;
-use std::rc::{Rc, Weak};
+use std::ops::ControlFlow;
+use std::rc::Rc;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum ObjectType {
     // TODO cfg[test]
     Test,
     Plane,
+    /// the unit disk in the xz plane
+    Disk,
+    /// the square from -1 to 1 on x and z in the xz plane
+    Rectangle,
+    /// the unit disk in the xz plane with a hole of radius `inner`
+    Annulus {
+        inner: RayTracerFloat,
+    },
     Sphere,
     Cube,
     Group(Vec<Rc<Object>>),
@@ -26,6 +40,16 @@ pub enum ObjectType {
         max_y: RayTracerFloat,
         closed: bool,
     },
+    /// shared geometry placed by this object's transform, optionally with its own material
+    Instance {
+        geometry: Rc<Object>,
+        material: Option<Box<Material>>,
+    },
+    /// a ring of radius `major` around the y axis, swept by a circle of radius `minor`
+    Torus {
+        major: RayTracerFloat,
+        minor: RayTracerFloat,
+    },
     Triangle {
         p1: Point,
         p2: Point,
@@ -36,84 +60,321 @@ pub enum ObjectType {
     },
 }
 
-#[derive(Debug, Clone)]
+impl ObjectType {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ObjectType::Test => "test",
+            ObjectType::Plane => "plane",
+            ObjectType::Disk => "disk",
+            ObjectType::Rectangle => "rectangle",
+            ObjectType::Annulus { .. } => "annulus",
+            ObjectType::Sphere => "sphere",
+            ObjectType::Cube => "cube",
+            ObjectType::Group(..) => "group",
+            ObjectType::Cylinder { .. } => "cylinder",
+            ObjectType::DoubleNappedCone { .. } => "cone",
+            ObjectType::Instance { .. } => "instance",
+            ObjectType::Torus { .. } => "torus",
+            ObjectType::Triangle { .. } => "triangle",
+        }
+    }
+}
+
+/// Where geometry reached through instances sits in the world, and the material standing in
+/// for its own, if any
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Placement<'a> {
+    pub to_world: InvertibleTransform,
+    pub material: Option<&'a Material>,
+}
+
+/// A shape, or a group of them. Groups own their children by value: a child that is still
+/// shared elsewhere when it is added or changed is copied, so editing one scene never
+/// disturbs another.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Object {
-    pub transform: Transform,
+    transform: InvertibleTransform,
+    /// `transform` composed with the transforms of every group containing the object
+    to_world: InvertibleTransform,
     pub material: Material,
     obj_type: ObjectType,
-    parent: Weak<Object>,
-}
-
-impl PartialEq for Object {
-    fn eq(&self, other: &Self) -> bool {
-        self.transform == other.transform && self.material == other.material
-        // reference equality for parent to prevent infinite recursion
-        && self.parent.ptr_eq(&other.parent)
-        && self.obj_type == other.obj_type
-    }
+    /// identifies the object among its siblings when looking it up by path
+    name: Option<String>,
+    /// whether `material` comes from the enclosing group rather than the object itself
+    inherits_material: bool,
 }
 
 impl Object {
-    pub fn test(transform: Transform, material: Material) -> Self {
-        Self {
+    fn try_new(transform: Transform, material: Material, obj_type: ObjectType) -> Result<Self> {
+        let transform = InvertibleTransform::new(transform)?;
+
+        Ok(Self {
             transform,
+            to_world: transform,
             material,
-            obj_type: ObjectType::Test,
-            parent: Weak::new(),
+            obj_type,
+            name: None,
+            inherits_material: false,
+        })
+    }
+
+    /// Makes the object take its material from whichever group it is added to. Groups built
+    /// without a material do this already; shapes are always built with one, so they keep it
+    /// unless told otherwise.
+    pub fn inheriting(mut self) -> Self {
+        self.inherits_material = true;
+        self
+    }
+
+    pub fn inherits_material(&self) -> bool {
+        self.inherits_material
+    }
+
+    /// Gives the object a material of its own, which any inheriting descendants pick up
+    pub fn set_material(&mut self, material: Material) {
+        self.material = material;
+        self.inherits_material = false;
+        self.pass_material_down();
+    }
+
+    /// Takes on `material` if inheriting, passing it on to inheriting descendants
+    fn inherit(&mut self, material: &Material) {
+        if self.inherits_material {
+            self.material = material.clone();
+            self.pass_material_down();
         }
     }
 
+    fn pass_material_down(&mut self) {
+        if let ObjectType::Group(children) = &mut self.obj_type {
+            for c in children.iter_mut().filter(|c| c.inherits_material) {
+                Rc::make_mut(c).inherit(&self.material);
+            }
+        }
+    }
+
+    pub fn named(mut self, name: impl Into<String>) -> Self {
+        self.set_name(name);
+        self
+    }
+
+    pub fn set_name(&mut self, name: impl Into<String>) {
+        self.name = Some(name.into());
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn test(transform: Transform, material: Material) -> Self {
+        Self::try_test(transform, material).unwrap_or_else(|e| panic!("invalid test shape: {}", e))
+    }
+
+    pub fn try_test(transform: Transform, material: Material) -> Result<Self> {
+        Self::try_new(transform, material, ObjectType::Test)
+    }
+
     pub fn plane(transform: Transform, material: Material) -> Self {
-        Self {
-            transform,
-            material,
-            obj_type: ObjectType::Plane,
-            parent: Weak::new(),
+        Self::try_plane(transform, material).unwrap_or_else(|e| panic!("invalid plane: {}", e))
+    }
+
+    pub fn try_plane(transform: Transform, material: Material) -> Result<Self> {
+        Self::try_new(transform, material, ObjectType::Plane)
+    }
+
+    pub fn disk(transform: Transform, material: Material) -> Self {
+        Self::try_disk(transform, material).unwrap_or_else(|e| panic!("invalid disk: {}", e))
+    }
+
+    pub fn try_disk(transform: Transform, material: Material) -> Result<Self> {
+        Self::try_new(transform, material, ObjectType::Disk)
+    }
+
+    pub fn rectangle(transform: Transform, material: Material) -> Self {
+        Self::try_rectangle(transform, material)
+            .unwrap_or_else(|e| panic!("invalid rectangle: {}", e))
+    }
+
+    pub fn try_rectangle(transform: Transform, material: Material) -> Result<Self> {
+        Self::try_new(transform, material, ObjectType::Rectangle)
+    }
+
+    pub fn annulus(transform: Transform, material: Material, inner: RayTracerFloat) -> Self {
+        Self::try_annulus(transform, material, inner)
+            .unwrap_or_else(|e| panic!("invalid annulus: {}", e))
+    }
+
+    /// A unit disk with a hole of radius `inner`, which must lie in [0, 1)
+    pub fn try_annulus(
+        transform: Transform,
+        material: Material,
+        inner: RayTracerFloat,
+    ) -> Result<Self> {
+        if !(0.0..1.).contains(&inner) {
+            return Err(Error::InvalidRadius(inner));
         }
+
+        Self::try_new(transform, material, ObjectType::Annulus { inner })
     }
 
     pub fn sphere(transform: Transform, material: Material) -> Self {
-        Self {
-            transform,
-            material,
-            obj_type: ObjectType::Sphere,
-            parent: Weak::new(),
-        }
+        Self::try_sphere(transform, material).unwrap_or_else(|e| panic!("invalid sphere: {}", e))
+    }
+
+    pub fn try_sphere(transform: Transform, material: Material) -> Result<Self> {
+        Self::try_new(transform, material, ObjectType::Sphere)
     }
 
     pub fn cube(transform: Transform, material: Material) -> Self {
-        Self {
-            transform,
-            material,
-            obj_type: ObjectType::Cube,
-            parent: Weak::new(),
+        Self::try_cube(transform, material).unwrap_or_else(|e| panic!("invalid cube: {}", e))
+    }
+
+    pub fn try_cube(transform: Transform, material: Material) -> Result<Self> {
+        Self::try_new(transform, material, ObjectType::Cube)
+    }
+
+    pub fn group(transform: Transform, children: Vec<Rc<Object>>) -> Rc<Self> {
+        Self::try_group(transform, children).unwrap_or_else(|e| panic!("invalid group: {}", e))
+    }
+
+    /// A group without a material of its own, which takes one from whichever group it is added
+    /// to and passes it on to its inheriting children
+    pub fn try_group(transform: Transform, children: Vec<Rc<Object>>) -> Result<Rc<Self>> {
+        let group = Self::try_new_group(transform, Material::default(), children)?;
+        Ok(Rc::new(group.inheriting()))
+    }
+
+    pub fn group_with_material(
+        transform: Transform,
+        material: Material,
+        children: Vec<Rc<Object>>,
+    ) -> Rc<Self> {
+        Self::try_group_with_material(transform, material, children)
+            .unwrap_or_else(|e| panic!("invalid group: {}", e))
+    }
+
+    /// A group with `material` as its own, which it keeps wherever it is added and passes on to
+    /// the children that inherit: groups built with `try_group` and shapes made `inheriting`.
+    /// Other shapes keep the material they were built with.
+    pub fn try_group_with_material(
+        transform: Transform,
+        material: Material,
+        children: Vec<Rc<Object>>,
+    ) -> Result<Rc<Self>> {
+        Self::try_new_group(transform, material, children).map(Rc::new)
+    }
+
+    fn try_new_group(
+        transform: Transform,
+        material: Material,
+        children: Vec<Rc<Object>>,
+    ) -> Result<Self> {
+        let mut group = Self::try_new(transform, material, ObjectType::Group(vec![]))?;
+
+        for child in children {
+            group.add_child(child)?;
         }
+
+        Ok(group)
     }
 
-    pub fn group(transform: Transform, mut children: Vec<Rc<Object>>) -> Rc<Self> {
-        let mut new_group = Rc::new(Self {
-            transform,
-            material: Material::default(),
-            obj_type: ObjectType::Group(vec![]),
-            parent: Weak::new(),
-        });
+    pub fn is_group(&self) -> bool {
+        matches!(self.obj_type, ObjectType::Group(..))
+    }
 
-        // SAFETY: Only called during single-threaded initialization; in particular, the children
-        // may have other referents, but they will not be dereferenced during this block
-        for child in children.iter_mut() {
-            unsafe {
-                // Required because the child may itself be a group with its own child-referents
-                Rc::get_mut_unchecked(child).parent = Rc::downgrade(&new_group);
+    /// A group's children, or nothing for other objects
+    pub fn children(&self) -> &[Rc<Object>] {
+        match &self.obj_type {
+            ObjectType::Group(children) => children,
+            _ => &[],
+        }
+    }
 
-                // Required because there will always be >1 reference to new_group at this point
-                match &mut Rc::get_mut_unchecked(&mut new_group).obj_type {
-                    ObjectType::Group(children) => children.push(child.clone()),
-                    _ => unreachable!(),
-                }
+    /// The position of the first child called `name`
+    pub fn child_index(&self, name: &str) -> Option<usize> {
+        self.children().iter().position(|c| c.name() == Some(name))
+    }
+
+    /// The first child called `name`, for editing; it is copied first if shared
+    pub fn child_mut(&mut self, name: &str) -> Option<&mut Object> {
+        let ObjectType::Group(children) = &mut self.obj_type else {
+            return None;
+        };
+
+        children
+            .iter_mut()
+            .find(|c| c.name() == Some(name))
+            .map(Rc::make_mut)
+    }
+
+    /// Appends `child` to this group, placing it in the group's space and handing it the
+    /// group's material if it inherits
+    pub fn add_child(&mut self, mut child: Rc<Object>) -> Result<()> {
+        let ObjectType::Group(children) = &mut self.obj_type else {
+            return Err(Error::NotAGroup);
+        };
+
+        let c = Rc::make_mut(&mut child);
+        c.place_under(self.to_world.matrix())?;
+        c.inherit(&self.material);
+        children.push(child);
+        Ok(())
+    }
+
+    /// Detaches and returns the child at `index`, which then stands on its own in world space,
+    /// keeping whatever material it last inherited
+    pub fn remove_child(&mut self, index: usize) -> Result<Rc<Object>> {
+        let ObjectType::Group(children) = &mut self.obj_type else {
+            return Err(Error::NotAGroup);
+        };
+
+        if index >= children.len() {
+            return Err(Error::NoSuchChild(index));
+        }
+
+        let mut child = children.remove(index);
+        Rc::make_mut(&mut child).place_under(&identity())?;
+        Ok(child)
+    }
+
+    /// Recomputes the world transforms of this object and its descendants for a new parent
+    /// whose own world transform is `parent_to_world`
+    fn place_under(&mut self, parent_to_world: &Transform) -> Result<()> {
+        self.to_world = InvertibleTransform::new(parent_to_world * self.transform.matrix())?;
+
+        if let ObjectType::Group(children) = &mut self.obj_type {
+            for c in children {
+                Rc::make_mut(c).place_under(self.to_world.matrix())?;
             }
         }
 
-        new_group
+        Ok(())
+    }
+
+    pub fn instance(
+        transform: Transform,
+        geometry: Rc<Object>,
+        material: Option<Material>,
+    ) -> Self {
+        Self::try_instance(transform, geometry, material)
+            .unwrap_or_else(|e| panic!("invalid instance: {}", e))
+    }
+
+    /// Places `geometry`, which may be shared by any number of instances, with `transform`.
+    /// If `material` is given it replaces the materials throughout the geometry.
+    pub fn try_instance(
+        transform: Transform,
+        geometry: Rc<Object>,
+        material: Option<Material>,
+    ) -> Result<Self> {
+        Self::try_new(
+            transform,
+            Material::default(),
+            ObjectType::Instance {
+                geometry,
+                material: material.map(Box::new),
+            },
+        )
     }
 
     pub fn cylinder(
@@ -123,16 +384,55 @@ impl Object {
         max_y: RayTracerFloat,
         closed: bool,
     ) -> Self {
-        Self {
+        Self::try_cylinder(transform, material, min_y, max_y, closed)
+            .unwrap_or_else(|e| panic!("invalid cylinder: {}", e))
+    }
+
+    pub fn try_cylinder(
+        transform: Transform,
+        material: Material,
+        min_y: RayTracerFloat,
+        max_y: RayTracerFloat,
+        closed: bool,
+    ) -> Result<Self> {
+        if min_y > max_y {
+            return Err(Error::InvalidBounds { min_y, max_y });
+        }
+
+        Self::try_new(
             transform,
             material,
-            obj_type: ObjectType::Cylinder {
+            ObjectType::Cylinder {
                 min_y,
                 max_y,
                 closed,
             },
-            parent: Weak::new(),
+        )
+    }
+
+    pub fn torus(
+        transform: Transform,
+        material: Material,
+        major: RayTracerFloat,
+        minor: RayTracerFloat,
+    ) -> Self {
+        Self::try_torus(transform, material, major, minor)
+            .unwrap_or_else(|e| panic!("invalid torus: {}", e))
+    }
+
+    pub fn try_torus(
+        transform: Transform,
+        material: Material,
+        major: RayTracerFloat,
+        minor: RayTracerFloat,
+    ) -> Result<Self> {
+        for r in [major, minor] {
+            if !(r > 0. && r.is_finite()) {
+                return Err(Error::InvalidRadius(r));
+            }
         }
+
+        Self::try_new(transform, material, ObjectType::Torus { major, minor })
     }
 
     pub fn cone(
@@ -142,16 +442,30 @@ impl Object {
         max_y: RayTracerFloat,
         closed: bool,
     ) -> Self {
-        Self {
+        Self::try_cone(transform, material, min_y, max_y, closed)
+            .unwrap_or_else(|e| panic!("invalid cone: {}", e))
+    }
+
+    pub fn try_cone(
+        transform: Transform,
+        material: Material,
+        min_y: RayTracerFloat,
+        max_y: RayTracerFloat,
+        closed: bool,
+    ) -> Result<Self> {
+        if min_y > max_y {
+            return Err(Error::InvalidBounds { min_y, max_y });
+        }
+
+        Self::try_new(
             transform,
             material,
-            obj_type: ObjectType::DoubleNappedCone {
+            ObjectType::DoubleNappedCone {
                 min_y,
                 max_y,
                 closed,
             },
-            parent: Weak::new(),
-        }
+        )
     }
 
     pub fn triangle(
@@ -161,40 +475,202 @@ impl Object {
         p2: Point,
         p3: Point,
     ) -> Self {
+        Self::try_triangle(transform, material, p1, p2, p3)
+            .unwrap_or_else(|e| panic!("invalid triangle: {}", e))
+    }
+
+    pub fn try_triangle(
+        transform: Transform,
+        material: Material,
+        p1: Point,
+        p2: Point,
+        p3: Point,
+    ) -> Result<Self> {
+        for p in [p1, p2, p3] {
+            if !p.is_point() {
+                return Err(Error::NotAPoint(p));
+            }
+        }
+
         let e1 = p2 - p1;
         let e2 = p3 - p1;
-        let normal = e2.cross(&e1).normalize();
+        let cross = e2.cross(&e1);
 
-        Self {
+        if cross.magnitude() < EPSILON {
+            return Err(Error::DegenerateTriangle);
+        }
+
+        Self::try_new(
             transform,
             material,
-            obj_type: ObjectType::Triangle {
+            ObjectType::Triangle {
                 p1,
                 p2,
                 p3,
                 e1,
                 e2,
-                normal,
+                normal: cross.normalize(),
             },
-            parent: Weak::new(),
-        }
+        )
+    }
+
+    pub fn transform(&self) -> &Transform {
+        self.transform.matrix()
+    }
+
+    /// Replaces this object's transform, leaving it unchanged if `transform` is not invertible
+    pub fn set_transform(&mut self, transform: Transform) -> Result<()> {
+        let parent_to_world = self.to_world.matrix() * self.transform.inverse();
+        self.transform = InvertibleTransform::new(transform)?;
+        self.place_under(&parent_to_world)
+    }
+
+    /// A detached copy of this object with a new transform and material. Copying a group
+    /// copies all of its descendants as well, since their world transforms and any materials
+    /// they inherit change along with the group's.
+    pub fn try_copy_with(&self, transform: Transform, material: Material) -> Result<Rc<Self>> {
+        let mut copy = self.clone();
+        copy.transform = InvertibleTransform::new(transform)?;
+        copy.set_material(material);
+        copy.place_under(&identity())?;
+        Ok(Rc::new(copy))
     }
 
     pub fn intersections(self: Rc<Self>, ray: &Ray) -> Intersections {
+        let mut buf = IntersectionBuffer::new();
+        self.intersect_into(ray, &mut buf);
+
+        Intersections::from_hits(buf.hits())
+    }
+
+    /// Adds every crossing of `ray` with this object (or, for groups, its descendants) to
+    /// `buf`, unsorted
+    pub fn intersect_into<'a>(self: &'a Rc<Self>, ray: &Ray, buf: &mut IntersectionBuffer<'a>) {
+        self.intersect_placed(ray, None, buf);
+    }
+
+    /// `intersect_into` for an object inside the geometry of the instance at `placement`
+    fn intersect_placed<'a>(
+        self: &'a Rc<Self>,
+        ray: &Ray,
+        placement: Option<Placement<'a>>,
+        buf: &mut IntersectionBuffer<'a>,
+    ) {
+        stats::record_intersection_test(self.obj_type.name());
+
         // "un-transforms" the ray so it's relative to the origin-centered,
         // unit-1-sized default for this Object
-        let local_ray = ray.transform(&self.transform.try_inverse().unwrap());
+        let local_ray = ray.transform(self.transform.inverse());
+
+        match &self.obj_type {
+            ObjectType::Group(children) => {
+                for c in children {
+                    c.intersect_placed(&local_ray, placement, buf);
+                }
+            }
+            ObjectType::Instance { geometry, material } => {
+                geometry.intersect_placed(&local_ray, Some(self.place(placement, material)), buf);
+            }
+            _ => {
+                let _ = self.local_hits(&local_ray, |t| {
+                    buf.push_placed(t, self, placement);
+                    ControlFlow::Continue(())
+                });
+            }
+        }
+    }
+
+    /// Where the geometry of this instance sits, given where the instance itself sits
+    fn place<'a>(
+        &self,
+        outer: Option<Placement<'a>>,
+        material: &'a Option<Box<Material>>,
+    ) -> Placement<'a> {
+        Placement {
+            to_world: outer.map_or(self.to_world, |o| o.to_world.compose(&self.to_world)),
+            material: material.as_deref().or(outer.and_then(|o| o.material)),
+        }
+    }
+
+    /// A standalone copy of this shape as it appears through the instance at `placement`
+    pub fn placed(&self, placement: &Placement) -> Self {
+        Self {
+            to_world: placement.to_world.compose(&self.to_world),
+            material: placement.material.unwrap_or(&self.material).clone(),
+            ..self.clone()
+        }
+    }
+
+    /// Whether `ray` hits some part of this object for which `accept` holds, strictly between
+    /// `EPSILON` and `max_t`. Stops at the first such hit and allocates nothing.
+    pub fn occludes(
+        &self,
+        ray: &Ray,
+        max_t: RayTracerFloat,
+        accept: &impl Fn(&Material) -> bool,
+    ) -> bool {
+        self.occludes_with(ray, max_t, accept, None)
+    }
 
+    /// `occludes`, with `material` standing in for the materials of the object's parts
+    fn occludes_with(
+        &self,
+        ray: &Ray,
+        max_t: RayTracerFloat,
+        accept: &impl Fn(&Material) -> bool,
+        material: Option<&Material>,
+    ) -> bool {
+        stats::record_intersection_test(self.obj_type.name());
+
+        let local_ray = ray.transform(self.transform.inverse());
+
+        match &self.obj_type {
+            ObjectType::Group(children) => children
+                .iter()
+                .any(|c| c.occludes_with(&local_ray, max_t, accept, material)),
+            ObjectType::Instance {
+                geometry,
+                material: own,
+            } => geometry.occludes_with(&local_ray, max_t, accept, own.as_deref().or(material)),
+            _ => {
+                accept(material.unwrap_or(&self.material))
+                    && self
+                        .local_hits(&local_ray, |t| {
+                            if EPSILON < t && t < max_t {
+                                ControlFlow::Break(())
+                            } else {
+                                ControlFlow::Continue(())
+                            }
+                        })
+                        .is_break()
+            }
+        }
+    }
+
+    /// Feeds the t of every point where `local_ray` crosses this primitive's surface to
+    /// `visit`, stopping as soon as it breaks. Groups have no surface of their own.
+    fn local_hits(
+        &self,
+        local_ray: &Ray,
+        mut visit: impl FnMut(RayTracerFloat) -> ControlFlow<()>,
+    ) -> ControlFlow<()> {
         match &self.obj_type {
-            ObjectType::Test => Intersections::empty(),
+            ObjectType::Test | ObjectType::Group(..) | ObjectType::Instance { .. } => {
+                ControlFlow::Continue(())
+            }
             ObjectType::Plane => {
                 if local_ray.direction.y().abs() < EPSILON {
-                    return Intersections::empty();
+                    return ControlFlow::Continue(());
                 }
 
-                let t = -local_ray.origin.y() / local_ray.direction.y();
-
-                Intersections::new(vec![Intersection::new(t, self).into()])
+                visit(-local_ray.origin.y() / local_ray.direction.y())
+            }
+            ObjectType::Disk => flat_hit(local_ray, |r2, _, _| r2 <= 1., visit),
+            ObjectType::Rectangle => {
+                flat_hit(local_ray, |_, x, z| x.abs() <= 1. && z.abs() <= 1., visit)
+            }
+            ObjectType::Annulus { inner } => {
+                flat_hit(local_ray, |r2, _, _| inner * inner <= r2 && r2 <= 1., visit)
             }
             ObjectType::Sphere => {
                 let sphere_to_ray = local_ray.origin - Point::origin();
@@ -205,13 +681,11 @@ impl Object {
                 let discriminant = b.powi(2) - 4. * a * c;
 
                 if discriminant < 0.0 {
-                    return Intersections::empty();
+                    return ControlFlow::Continue(());
                 }
 
-                Intersections::new(vec![
-                    Intersection::new((-b - discriminant.sqrt()) / (2.0 * a), self.clone()).into(),
-                    Intersection::new((-b + discriminant.sqrt()) / (2.0 * a), self).into(),
-                ])
+                visit((-b - discriminant.sqrt()) / (2.0 * a))?;
+                visit((-b + discriminant.sqrt()) / (2.0 * a))
             }
             ObjectType::Cube => {
                 /// Returns tmin, tmax for a particular axis's origin/direction components
@@ -241,21 +715,19 @@ impl Object {
                     (tmin, tmax)
                 }
 
-                let (xtmin, xtmax) = check_axis(ray.origin.x(), ray.direction.x());
-                let (ytmin, ytmax) = check_axis(ray.origin.y(), ray.direction.y());
-                let (ztmin, ztmax) = check_axis(ray.origin.z(), ray.direction.z());
+                let (xtmin, xtmax) = check_axis(local_ray.origin.x(), local_ray.direction.x());
+                let (ytmin, ytmax) = check_axis(local_ray.origin.y(), local_ray.direction.y());
+                let (ztmin, ztmax) = check_axis(local_ray.origin.z(), local_ray.direction.z());
 
                 let tmin = xtmin.max(ytmin.max(ztmin));
                 let tmax = xtmax.min(ytmax.min(ztmax));
 
                 // erratum: tmax < 0 => intersections in the _opposite_ direction
                 if tmax < 0.0 || tmin > tmax {
-                    Intersections::empty()
+                    ControlFlow::Continue(())
                 } else {
-                    Intersections::new(vec![
-                        Intersection::new(tmin, self.clone()).into(),
-                        Intersection::new(tmax, self).into(),
-                    ])
+                    visit(tmin)?;
+                    visit(tmax)
                 }
             }
             ObjectType::Cylinder {
@@ -264,7 +736,6 @@ impl Object {
                 closed,
             } => {
                 let a = local_ray.direction.x().powi(2) + local_ray.direction.z().powi(2);
-                let mut intersections: Vec<Rc<Intersection>> = vec![];
 
                 if a.abs() >= EPSILON {
                     // ray is not parallel to the y axis
@@ -274,7 +745,7 @@ impl Object {
                     let discriminant = b.powi(2) - 4.0 * a * c;
 
                     if discriminant < 0.0 {
-                        return Intersections::empty();
+                        return ControlFlow::Continue(());
                     }
 
                     let t0 = (-b - discriminant.sqrt()) / (2.0 * a);
@@ -284,11 +755,11 @@ impl Object {
                     let y1 = local_ray.origin.y() + t1 * local_ray.direction.y();
 
                     if *min_y < y0 && y0 < *max_y {
-                        intersections.push(Intersection::new(t0, self.clone()).into());
+                        visit(t0)?;
                     }
 
                     if *min_y < y1 && y1 < *max_y {
-                        intersections.push(Intersection::new(t1, self.clone()).into());
+                        visit(t1)?;
                     }
                 }
 
@@ -302,16 +773,16 @@ impl Object {
                     let tmin = (min_y - local_ray.origin.y()) / local_ray.direction.y();
                     let tmax = (max_y - local_ray.origin.y()) / local_ray.direction.y();
 
-                    if ray_within_cylinder_at_t(&local_ray, tmin) {
-                        intersections.push(Intersection::new(tmin, self.clone()).into());
+                    if ray_within_cylinder_at_t(local_ray, tmin) {
+                        visit(tmin)?;
                     }
 
-                    if ray_within_cylinder_at_t(&local_ray, tmax) {
-                        intersections.push(Intersection::new(tmax, self.clone()).into());
+                    if ray_within_cylinder_at_t(local_ray, tmax) {
+                        visit(tmax)?;
                     }
                 }
 
-                Intersections::new(intersections)
+                ControlFlow::Continue(())
             }
             ObjectType::DoubleNappedCone {
                 min_y,
@@ -328,20 +799,17 @@ impl Object {
                 let c = local_ray.origin.x().powi(2) - local_ray.origin.y().powi(2)
                     + local_ray.origin.z().powi(2);
 
-                let mut intersections: Vec<Rc<Intersection>> = vec![];
-
                 if a.abs() < EPSILON {
                     if b.abs() >= EPSILON {
                         // ray is parallel to the "pointy part" of the cone
-                        let t = -c / (2.0 * b);
-                        intersections.push(Intersection::new(t, self.clone()).into());
+                        visit(-c / (2.0 * b))?;
                     }
                 } else {
                     // ray intersects cone "normally"
                     let discriminant = b.powi(2) - 4.0 * a * c;
 
                     if discriminant < 0.0 {
-                        return Intersections::empty();
+                        return ControlFlow::Continue(());
                     }
 
                     let t0 = (-b - discriminant.sqrt()) / (2.0 * a);
@@ -351,11 +819,11 @@ impl Object {
                     let y1 = local_ray.origin.y() + t1 * local_ray.direction.y();
 
                     if *min_y < y0 && y0 < *max_y {
-                        intersections.push(Intersection::new(t0, self.clone()).into());
+                        visit(t0)?;
                     }
 
                     if *min_y < y1 && y1 < *max_y {
-                        intersections.push(Intersection::new(t1, self.clone()).into());
+                        visit(t1)?;
                     }
                 }
 
@@ -369,69 +837,78 @@ impl Object {
                     let tmin = (min_y - local_ray.origin.y()) / local_ray.direction.y();
                     let tmax = (max_y - local_ray.origin.y()) / local_ray.direction.y();
 
-                    if ray_within_cone_at_t(&local_ray, tmin, *min_y) {
-                        intersections.push(Intersection::new(tmin, self.clone()).into());
+                    if ray_within_cone_at_t(local_ray, tmin, *min_y) {
+                        visit(tmin)?;
                     }
 
-                    if ray_within_cone_at_t(&local_ray, tmax, *max_y) {
-                        intersections.push(Intersection::new(tmax, self.clone()).into());
+                    if ray_within_cone_at_t(local_ray, tmax, *max_y) {
+                        visit(tmax)?;
                     }
                 }
 
-                Intersections::new(intersections)
+                ControlFlow::Continue(())
             }
-            ObjectType::Group(children) => {
-                let mut all_intersections: Vec<Rc<Intersection>> = vec![];
-
-                for c in children {
-                    let ints = c.clone().intersections(&local_ray);
-
-                    for c in ints.ints() {
-                        all_intersections.push(c.clone());
-                    }
+            ObjectType::Torus { major, minor } => {
+                // solving from where the ray enters the bounding box keeps the quartic's
+                // coefficients small even for rays that start far away
+                let Some((enter, _)) = self.bounds().span(local_ray) else {
+                    return ControlFlow::Continue(());
+                };
+
+                // a unit direction keeps the leading coefficient at 1 however the torus is
+                // scaled; roots are then distances, converted back to t below
+                let length = local_ray.direction.magnitude();
+                let o = local_ray.position(enter) - Point::origin();
+                let d = local_ray.direction / length;
+                let four_rr = 4. * major * major;
+
+                let od = o.dot(&d);
+                let e = o.dot(&o) - major * major - minor * minor;
+
+                let roots = quartic(
+                    1.,
+                    4. * od,
+                    2. * e + 4. * od * od + four_rr * d.y() * d.y(),
+                    4. * od * e + 2. * four_rr * o.y() * d.y(),
+                    e * e - four_rr * (minor * minor - o.y() * o.y()),
+                );
+
+                for distance in roots.iter() {
+                    visit(enter + distance / length)?;
                 }
 
-                Intersections::new(all_intersections)
+                ControlFlow::Continue(())
             }
             ObjectType::Triangle { p1, e1, e2, .. } => {
                 let cross_e2 = local_ray.direction.cross(e2);
                 let determinant = e1.dot(&cross_e2);
 
                 if determinant.abs() < EPSILON {
-                    return Intersections::empty();
+                    return ControlFlow::Continue(());
                 }
 
                 let f = 1.0 / determinant;
-                let p1_to_origin = ray.origin - *p1;
+                let p1_to_origin = local_ray.origin - *p1;
                 let u = f * p1_to_origin.dot(&cross_e2);
 
-                if u < 0.0 || u > 1.0 {
-                    return Intersections::empty();
+                if !(0.0..=1.0).contains(&u) {
+                    return ControlFlow::Continue(());
                 }
 
                 let origin_cross_e1 = p1_to_origin.cross(e1);
-                let v = f * ray.direction.dot(&origin_cross_e1);
+                let v = f * local_ray.direction.dot(&origin_cross_e1);
 
                 if v < 0.0 || (u + v) > 1.0 {
-                    return Intersections::empty();
+                    return ControlFlow::Continue(());
                 }
 
-                let t = f * e2.dot(&origin_cross_e1);
-
-                Intersections::new(vec![Intersection::new(t, self.clone()).into()])
+                visit(f * e2.dot(&origin_cross_e1))
             }
         }
     }
 
     pub fn world_point_to_local(&self, world_point: Point) -> Point {
-        let p = if let Some(parent) = self.parent.upgrade() {
-            parent.world_point_to_local(world_point)
-        } else {
-            world_point
-        };
-
-        let inverse = self.transform.try_inverse().unwrap();
-        p.transform(&inverse)
+        world_point.transform(self.to_world.inverse())
     }
 
     fn local_normal_to_world(&self, local_normal: Vector) -> Vector {
@@ -440,17 +917,10 @@ impl Object {
             return local_normal;
         }
 
-        let inverse_transpose = self.transform.try_inverse().unwrap().transpose();
-        let transformed_norm = local_normal
-            .transform(&inverse_transpose)
+        local_normal
+            .transform(self.to_world.inverse_transpose())
             .to_vector()
-            .normalize();
-
-        if let Some(parent) = self.parent.upgrade() {
-            parent.local_normal_to_world(transformed_norm)
-        } else {
-            transformed_norm
-        }
+            .normalize()
     }
 
     pub fn normal_at(&self, p: Point) -> Vector {
@@ -458,7 +928,10 @@ impl Object {
 
         let local_normal = match self.obj_type {
             ObjectType::Test => local_point.to_vector(),
-            ObjectType::Plane => Vector::vector(0., 1., 0.),
+            ObjectType::Plane
+            | ObjectType::Disk
+            | ObjectType::Rectangle
+            | ObjectType::Annulus { .. } => Vector::vector(0., 1., 0.),
             ObjectType::Sphere => local_point - Point::origin(),
             ObjectType::Cube => {
                 let x = local_point.x().abs();
@@ -498,12 +971,293 @@ impl Object {
                     Vector::vector(local_point.x(), y, local_point.z())
                 }
             }
-            ObjectType::Group(..) => unimplemented!(),
+            ObjectType::Group(..) | ObjectType::Instance { .. } => {
+                return self
+                    .nearest_surface(p)
+                    .map_or(Vector::vector(0., 0., 0.), |s| s.normal_at(p));
+            }
+            ObjectType::Torus { major, minor } => {
+                let p = local_point - Point::origin();
+                let s = p.dot(&p) - major * major - minor * minor;
+
+                Vector::vector(p.x() * s, p.y() * (s + 2. * major * major), p.z() * s)
+            }
             ObjectType::Triangle { normal, .. } => normal,
         };
 
         self.local_normal_to_world(local_normal)
     }
+
+    /// Maps `u` and `v` in [0, 1) evenly over the surface of a disk, rectangle or annulus in
+    /// world space, so that they can stand in for area lights; `None` for other shapes
+    pub fn surface_point(&self, u: RayTracerFloat, v: RayTracerFloat) -> Option<Point> {
+        let local = match self.obj_type {
+            ObjectType::Rectangle => Point::point(2. * u - 1., 0., 2. * v - 1.),
+            ObjectType::Disk => ring_point(u, v, 0.),
+            ObjectType::Annulus { inner } => ring_point(u, v, inner),
+            _ => return None,
+        };
+
+        Some(local.transform(self.to_world.matrix()))
+    }
+
+    /// The box around this object in its own space, before its transform is applied
+    pub fn bounds(&self) -> Bounds {
+        let cube = Bounds::new(Point::point(-1., -1., -1.), Point::point(1., 1., 1.));
+
+        match &self.obj_type {
+            ObjectType::Test | ObjectType::Sphere | ObjectType::Cube => cube,
+            ObjectType::Disk | ObjectType::Rectangle | ObjectType::Annulus { .. } => {
+                Bounds::new(Point::point(-1., 0., -1.), Point::point(1., 0., 1.))
+            }
+            ObjectType::Plane => Bounds::new(
+                Point::point(-RayTracerFloat::INFINITY, 0., -RayTracerFloat::INFINITY),
+                Point::point(RayTracerFloat::INFINITY, 0., RayTracerFloat::INFINITY),
+            ),
+            ObjectType::Group(children) => children.iter().fold(Bounds::empty(), |b, c| {
+                b.union(&c.bounds().transform(c.transform()))
+            }),
+            ObjectType::Cylinder { min_y, max_y, .. } => {
+                Bounds::new(Point::point(-1., *min_y, -1.), Point::point(1., *max_y, 1.))
+            }
+            ObjectType::DoubleNappedCone { min_y, max_y, .. } => {
+                let r = min_y.abs().max(max_y.abs());
+                Bounds::new(Point::point(-r, *min_y, -r), Point::point(r, *max_y, r))
+            }
+            ObjectType::Instance { geometry, .. } => {
+                geometry.bounds().transform(geometry.transform())
+            }
+            ObjectType::Torus { major, minor } => {
+                let r = major + minor;
+                Bounds::new(Point::point(-r, -minor, -r), Point::point(r, *minor, r))
+            }
+            ObjectType::Triangle { p1, p2, p3, .. } => [p1, p2, p3]
+                .into_iter()
+                .fold(Bounds::empty(), |b, p| b.union(&Bounds::new(*p, *p))),
+        }
+    }
+
+    /// The shape closest to `p` among this object and its descendants, as it sits in the
+    /// world; `None` for empty groups
+    pub fn nearest_surface(&self, p: Point) -> Option<Object> {
+        let mut nearest = None;
+        self.nearest_placed(p, None, &mut nearest);
+        nearest.map(|(_, o)| o)
+    }
+
+    fn nearest_placed(
+        &self,
+        p: Point,
+        placement: Option<Placement>,
+        nearest: &mut Option<(RayTracerFloat, Object)>,
+    ) {
+        let placed;
+        let shape = match (&self.obj_type, placement) {
+            (ObjectType::Group(children), _) => {
+                children
+                    .iter()
+                    .for_each(|c| c.nearest_placed(p, placement, nearest));
+                return;
+            }
+            (ObjectType::Instance { geometry, material }, _) => {
+                geometry.nearest_placed(p, Some(self.place(placement, material)), nearest);
+                return;
+            }
+            (_, Some(pl)) => {
+                placed = self.placed(&pl);
+                &placed
+            }
+            (_, None) => self,
+        };
+
+        let on_surface = shape
+            .local_surface_point(shape.world_point_to_local(p))
+            .transform(shape.to_world.matrix());
+        let distance = (on_surface - p).magnitude();
+
+        if nearest.as_ref().is_none_or(|(d, _)| distance < *d) {
+            *nearest = Some((distance, shape.clone()));
+        }
+    }
+
+    /// The point on this shape's untransformed surface closest to `p`
+    fn local_surface_point(&self, p: Point) -> Point {
+        let (x, y, z) = (p.x(), p.y(), p.z());
+
+        match self.obj_type {
+            ObjectType::Test | ObjectType::Group(..) | ObjectType::Instance { .. } => p,
+            ObjectType::Plane => Point::point(x, 0., z),
+            ObjectType::Rectangle => Point::point(x.clamp(-1., 1.), 0., z.clamp(-1., 1.)),
+            ObjectType::Disk => nearest_in_ring(x, z, 0.),
+            ObjectType::Annulus { inner } => nearest_in_ring(x, z, inner),
+            ObjectType::Sphere if p == Point::origin() => Point::point(0., 1., 0.),
+            ObjectType::Sphere => Point::origin() + (p - Point::origin()).normalize(),
+            ObjectType::Cube => {
+                let clamped = [x, y, z].map(|c| c.clamp(-1., 1.));
+
+                if clamped != [x, y, z] {
+                    Point::point(clamped[0], clamped[1], clamped[2])
+                } else {
+                    // inside: out through the nearest face
+                    let mut c = [x, y, z];
+                    let (i, _) = c
+                        .iter()
+                        .enumerate()
+                        .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+                        .unwrap();
+                    c[i] = if c[i] < 0. { -1. } else { 1. };
+                    Point::point(c[0], c[1], c[2])
+                }
+            }
+            ObjectType::Cylinder {
+                min_y,
+                max_y,
+                closed,
+            } => {
+                let r = x.hypot(z);
+                let (ux, uz) = if r > 0. { (x / r, z / r) } else { (1., 0.) };
+                let side = Point::point(ux, y.clamp(min_y, max_y), uz);
+                let caps = [min_y, max_y]
+                    .into_iter()
+                    .filter(|cap| closed && cap.is_finite())
+                    .map(|cap| Point::point(ux * r.min(1.), cap, uz * r.min(1.)));
+
+                closest(p, std::iter::once(side).chain(caps))
+            }
+            ObjectType::DoubleNappedCone {
+                min_y,
+                max_y,
+                closed,
+            } => {
+                let r = x.hypot(z);
+                let (ux, uz) = if r > 0. { (x / r, z / r) } else { (1., 0.) };
+                let at = |radius: RayTracerFloat, y| Point::point(ux * radius, y, uz * radius);
+
+                // the upper nappe is y = r and the lower y = -r, each cut off by the bounds
+                let upper = (max_y >= 0.).then(|| {
+                    let t = ((r + y) / 2.).clamp(min_y.max(0.), max_y);
+                    at(t, t)
+                });
+                let lower = (min_y <= 0.).then(|| {
+                    let t = ((r - y) / 2.).clamp((-max_y).max(0.), -min_y);
+                    at(t, -t)
+                });
+                let caps = [min_y, max_y]
+                    .into_iter()
+                    .filter(|cap| closed && cap.is_finite())
+                    .map(|cap| at(r.min(cap.abs()), cap));
+
+                closest(p, upper.into_iter().chain(lower).chain(caps))
+            }
+            ObjectType::Torus { major, minor } => {
+                // the nearest point of the ring swept around, then out towards `p`
+                let r = x.hypot(z);
+                let ring = if r > 0. {
+                    Point::point(x / r * major, 0., z / r * major)
+                } else {
+                    Point::point(major, 0., 0.)
+                };
+                let out = p - ring;
+
+                if out.magnitude() > 0. {
+                    ring + out.normalize() * minor
+                } else {
+                    ring + Vector::vector(0., minor, 0.)
+                }
+            }
+            ObjectType::Triangle { p1, p2, p3, .. } => closest_on_triangle(p, p1, p2, p3),
+        }
+    }
+}
+
+/// Visits the crossing of `local_ray` with the xz plane if `within` holds for the squared
+/// distance from the origin and the x and z of the point crossed
+fn flat_hit(
+    local_ray: &Ray,
+    within: impl Fn(RayTracerFloat, RayTracerFloat, RayTracerFloat) -> bool,
+    mut visit: impl FnMut(RayTracerFloat) -> ControlFlow<()>,
+) -> ControlFlow<()> {
+    if local_ray.direction.y().abs() < EPSILON {
+        return ControlFlow::Continue(());
+    }
+
+    let t = -local_ray.origin.y() / local_ray.direction.y();
+    let p = local_ray.position(t);
+
+    if within(p.x() * p.x() + p.z() * p.z(), p.x(), p.z()) {
+        visit(t)
+    } else {
+        ControlFlow::Continue(())
+    }
+}
+
+/// The point of the ring between radii `inner` and 1 in the xz plane closest to (x, 0, z)
+fn nearest_in_ring(x: RayTracerFloat, z: RayTracerFloat, inner: RayTracerFloat) -> Point {
+    let r = x.hypot(z);
+
+    if r == 0. {
+        Point::point(inner, 0., 0.)
+    } else {
+        let scale = r.clamp(inner, 1.) / r;
+        Point::point(x * scale, 0., z * scale)
+    }
+}
+
+/// Maps `u` and `v` in [0, 1) evenly by area over the ring between radii `inner` and 1
+fn ring_point(u: RayTracerFloat, v: RayTracerFloat, inner: RayTracerFloat) -> Point {
+    let r = (inner * inner + u * (1. - inner * inner)).sqrt();
+    let (sin, cos) = (TAU * v).sin_cos();
+    Point::point(r * cos, 0., r * sin)
+}
+
+/// Whichever of `candidates` is closest to `p`
+fn closest(p: Point, candidates: impl Iterator<Item = Point>) -> Point {
+    candidates
+        .min_by(|a, b| (*a - p).magnitude().total_cmp(&(*b - p).magnitude()))
+        .unwrap_or(p)
+}
+
+/// The point of triangle `abc` closest to `p`, found by working out which vertex, edge or
+/// face region `p` projects into
+fn closest_on_triangle(p: Point, a: Point, b: Point, c: Point) -> Point {
+    let ab = b - a;
+    let ac = c - a;
+
+    let ap = p - a;
+    let (d1, d2) = (ab.dot(&ap), ac.dot(&ap));
+    if d1 <= 0. && d2 <= 0. {
+        return a;
+    }
+
+    let bp = p - b;
+    let (d3, d4) = (ab.dot(&bp), ac.dot(&bp));
+    if d3 >= 0. && d4 <= d3 {
+        return b;
+    }
+
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0. && d1 >= 0. && d3 <= 0. {
+        return a + ab * (d1 / (d1 - d3));
+    }
+
+    let cp = p - c;
+    let (d5, d6) = (ab.dot(&cp), ac.dot(&cp));
+    if d6 >= 0. && d5 <= d6 {
+        return c;
+    }
+
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0. && d2 >= 0. && d6 <= 0. {
+        return a + ac * (d2 / (d2 - d6));
+    }
+
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0. && d4 - d3 >= 0. && d5 - d6 >= 0. {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+
+    let denom = va + vb + vc;
+    a + ab * (vb / denom) + ac * (vc / denom)
 }
 
 // TODO cfg(test)
@@ -539,9 +1293,13 @@ mod test {
     use approx::assert_abs_diff_eq;
 
     use crate::{
-        material::Material,
+        bounds::Bounds,
+        color::Color,
+        error::Error,
+        material::{Material, MaterialBuilder},
         objects::{default_sphere, ObjectType},
         ray::Ray,
+        stats::collect,
         transforms::{identity, rotation, scaling, translation, RotationAxis},
         tuple::{Point, Vector},
         util::{test::glass_sphere, RayTracerFloat, EPSILON},
@@ -582,6 +1340,71 @@ mod test {
         )
     }
 
+    #[test]
+    fn set_transform_rejects_non_invertible() {
+        let mut s = default_sphere();
+        s.set_transform(translation(1., 2., 3.)).unwrap();
+
+        assert!(s.set_transform(scaling(0., 1., 1.)).is_err());
+        assert_eq!(*s.transform(), translation(1., 2., 3.));
+    }
+
+    #[test]
+    fn copying_a_group_leaves_original_alone() {
+        let g = Object::group(identity(), vec![Rc::new(default_sphere())]);
+        let copy = g
+            .try_copy_with(translation(1., 0., 0.), Material::default())
+            .unwrap();
+
+        let p = Point::point(1., 0., 0.);
+
+        assert_eq!(*copy.transform(), translation(1., 0., 0.));
+        assert_eq!(g.children()[0].world_point_to_local(p), p);
+        assert_eq!(copy.children()[0].world_point_to_local(p), Point::origin());
+    }
+
+    #[test]
+    fn try_constructors_reject_invalid_input() {
+        assert_eq!(
+            Object::try_sphere(scaling(1., 0., 1.), Material::default()),
+            Err(Error::NonInvertibleTransform)
+        );
+
+        assert_eq!(
+            Object::try_cylinder(identity(), Material::default(), 2., 1., true),
+            Err(Error::InvalidBounds {
+                min_y: 2.,
+                max_y: 1.
+            })
+        );
+
+        assert_eq!(
+            Object::try_triangle(
+                identity(),
+                Material::default(),
+                Point::point(0., 0., 0.),
+                Point::point(1., 1., 1.),
+                Point::point(2., 2., 2.),
+            ),
+            Err(Error::DegenerateTriangle)
+        );
+    }
+
+    #[test]
+    fn inverse_computed_once_per_object() {
+        let s = Rc::new(Object::sphere(translation(0., 0., 5.), Material::default()));
+        let r = Ray::new(Point::point(0., 0., 0.), Vector::vector(0., 0., 1.));
+
+        let (_, stats) = collect(|| {
+            for _ in 0..10 {
+                s.clone().intersections(&r);
+                s.normal_at(Point::point(0., 0., 4.));
+            }
+        });
+
+        assert_eq!(stats.inverse_computations, 0);
+    }
+
     #[test]
     fn glass_sphere_properties() {
         let gs = glass_sphere();
@@ -953,11 +1776,9 @@ mod test {
 
         let g = Object::group(identity(), vec![t1.into(), t2.into()]);
         assert!(g.obj_type.children().len() == 2);
-        assert!(g.parent.upgrade().is_none());
 
         for c in g.obj_type.children() {
             assert_eq!(c.obj_type, ObjectType::Test);
-            assert_eq!(c.parent.upgrade().unwrap(), g);
         }
     }
 
@@ -1015,10 +1836,11 @@ mod test {
             Material::default(),
         ));
 
-        let _group = Object::group(
+        let group = Object::group(
             rotation(RotationAxis::Y, FRAC_PI_2),
-            vec![Object::group(scaling(2.0, 2.0, 2.0), vec![s.clone()])],
+            vec![Object::group(scaling(2.0, 2.0, 2.0), vec![s])],
         );
+        let s = &group.children()[0].children()[0];
 
         assert_abs_diff_eq!(
             s.world_point_to_local(Point::point(-2.0, 0.0, -10.0)),
@@ -1033,10 +1855,11 @@ mod test {
             Material::default(),
         ));
 
-        let _group = Object::group(
+        let group = Object::group(
             rotation(RotationAxis::Y, FRAC_PI_2),
-            vec![Object::group(scaling(1.0, 2.0, 3.0), vec![s.clone()])],
+            vec![Object::group(scaling(1.0, 2.0, 3.0), vec![s])],
         );
+        let s = &group.children()[0].children()[0];
 
         let frac_sqrt_3_3 = 3.0f64.sqrt() / 3.0;
         let local_normal = Vector::vector(frac_sqrt_3_3, frac_sqrt_3_3, frac_sqrt_3_3);
@@ -1053,10 +1876,11 @@ mod test {
             Material::default(),
         ));
 
-        let _group = Object::group(
+        let group = Object::group(
             rotation(RotationAxis::Y, FRAC_PI_2),
-            vec![Object::group(scaling(1.0, 2.0, 3.0), vec![s.clone()])],
+            vec![Object::group(scaling(1.0, 2.0, 3.0), vec![s])],
         );
+        let s = &group.children()[0].children()[0];
 
         let global_point = Point::point(1.7321, 1.1547, -5.5774);
         assert_abs_diff_eq!(
@@ -1113,7 +1937,7 @@ mod test {
             Point::point(1., 0., 0.),
         ));
 
-        for p in vec![
+        for p in [
             Point::point(0.0, 0.5, 0.0),
             Point::point(-0.5, -0.75, 0.0),
             Point::point(0.5, 0.25, 0.0),
@@ -1178,4 +2002,568 @@ mod test {
         assert_eq!(xs.ints().len(), 1);
         assert_eq!(xs.ints()[0].t, 2.0);
     }
+
+    #[test]
+    fn transformed_cube_uses_local_ray() {
+        let c = Rc::new(Object::cube(translation(5., 0., 0.), Material::default()));
+        let r = Ray::new(Point::point(5., 0., -5.), Vector::vector(0., 0., 1.));
+
+        let xs = c.intersections(&r);
+        assert_eq!(xs.ints().len(), 2);
+        assert_eq!(xs.ints()[0].t, 4.);
+        assert_eq!(xs.ints()[1].t, 6.);
+    }
+
+    #[test]
+    fn occlusion_only_counts_hits_in_range() {
+        let s = default_sphere();
+        let r = Ray::new(Point::point(0., 0., -5.), Vector::vector(0., 0., 1.));
+        let any = |_: &Material| true;
+
+        assert!(s.occludes(&r, 10., &any));
+        assert!(s.occludes(&r, 5., &any));
+        assert!(!s.occludes(&r, 4., &any));
+        assert!(!s.occludes(&r, 10., &|m: &Material| m.transparency > 0.));
+
+        // hits behind the origin don't count
+        let away = Ray::new(Point::point(0., 0., 5.), Vector::vector(0., 0., 1.));
+        assert!(!s.occludes(&away, 100., &any));
+    }
+
+    #[test]
+    fn occlusion_looks_inside_groups() {
+        let g = Object::group(
+            scaling(2., 2., 2.),
+            vec![Rc::new(Object::sphere(
+                translation(5., 0., 0.),
+                Material::default(),
+            ))],
+        );
+
+        let any = |_: &Material| true;
+        let hits = Ray::new(Point::point(10., 0., -10.), Vector::vector(0., 0., 1.));
+        let misses = Ray::new(Point::point(5., 0., -10.), Vector::vector(0., 0., 1.));
+
+        assert!(g.occludes(&hits, 20., &any));
+        assert!(!g.occludes(&misses, 20., &any));
+    }
+
+    #[test]
+    fn children_can_be_added_and_removed_later() {
+        let mut g = Object::group(translation(0., 0., 5.), vec![]);
+        let shared = Rc::new(default_sphere());
+
+        Rc::make_mut(&mut g).add_child(shared.clone()).unwrap();
+        Rc::make_mut(&mut g)
+            .add_child(Rc::new(default_cube()))
+            .unwrap();
+        assert_eq!(g.children().len(), 2);
+
+        // the group places its own copy; the original is untouched
+        let p = Point::point(0., 0., 5.);
+        assert_eq!(g.children()[0].world_point_to_local(p), Point::origin());
+        assert_eq!(shared.world_point_to_local(p), p);
+
+        let removed = Rc::make_mut(&mut g).remove_child(0).unwrap();
+        assert_eq!(g.children().len(), 1);
+        assert_eq!(g.children()[0].obj_type, ObjectType::Cube);
+        assert_eq!(removed.world_point_to_local(p), p);
+
+        assert_eq!(
+            Rc::make_mut(&mut g).remove_child(1),
+            Err(Error::NoSuchChild(1))
+        );
+        assert_eq!(
+            default_sphere().add_child(Rc::new(default_cube())),
+            Err(Error::NotAGroup)
+        );
+    }
+
+    #[test]
+    fn moving_a_group_moves_its_descendants() {
+        let mut g = Object::group(
+            identity(),
+            vec![Object::group(
+                scaling(2., 2., 2.),
+                vec![Rc::new(Object::sphere(
+                    translation(5., 0., 0.),
+                    Material::default(),
+                ))],
+            )],
+        );
+
+        Rc::make_mut(&mut g)
+            .set_transform(rotation(RotationAxis::Y, FRAC_PI_2))
+            .unwrap();
+
+        let s = &g.children()[0].children()[0];
+        assert_abs_diff_eq!(
+            s.world_point_to_local(Point::point(-2.0, 0.0, -10.0)),
+            Point::point(0.0, 0.0, -1.0)
+        );
+    }
+
+    #[test]
+    fn instances_share_their_geometry() {
+        let mesh = Object::group(
+            scaling(2., 2., 2.),
+            vec![Rc::new(basic_triangle(
+                Point::point(0., 1., 0.),
+                Point::point(-1., 0., 0.),
+                Point::point(1., 0., 0.),
+            ))],
+        );
+
+        let instances: Vec<_> = (0..1000)
+            .map(|i| Object::instance(translation(i as RayTracerFloat, 0., 0.), mesh.clone(), None))
+            .collect();
+
+        assert_eq!(Rc::strong_count(&mesh), 1001);
+        assert!(instances.iter().all(|i| match &i.obj_type {
+            ObjectType::Instance { geometry, .. } => Rc::ptr_eq(geometry, &mesh),
+            _ => false,
+        }));
+    }
+
+    #[test]
+    fn instances_match_transformed_copies() {
+        let geometry = Object::group(
+            scaling(2., 2., 2.),
+            vec![Rc::new(Object::sphere(
+                translation(1., 0., 0.),
+                Material::default(),
+            ))],
+        );
+        let placement = rotation(RotationAxis::Y, FRAC_PI_2);
+        let instance = Rc::new(Object::instance(placement, geometry.clone(), None));
+        let copy = geometry
+            .try_copy_with(placement * scaling(2., 2., 2.), Material::default())
+            .unwrap();
+
+        let r = Ray::new(Point::point(-10., 0., -2.), Vector::vector(1., 0., 0.));
+        let xs = instance.intersections(&r);
+        let expected = copy.intersections(&r);
+
+        assert_eq!(xs.ints().len(), 2);
+        for (i, e) in xs.ints().iter().zip(expected.ints()) {
+            assert_abs_diff_eq!(i.t, e.t);
+            let p = r.position(i.t);
+            assert_abs_diff_eq!(i.object.normal_at(p), e.object.normal_at(p));
+        }
+    }
+
+    #[test]
+    fn innermost_instance_material_wins() {
+        let red = MaterialBuilder::default()
+            .color(Color::new(1., 0., 0.))
+            .build();
+        let blue = MaterialBuilder::default()
+            .color(Color::new(0., 0., 1.))
+            .build();
+        let sphere = Rc::new(default_sphere());
+        let r = Ray::new(Point::point(0., 0., -5.), Vector::vector(0., 0., 1.));
+
+        let plain = Rc::new(Object::instance(identity(), sphere.clone(), None));
+        assert_eq!(
+            plain.intersections(&r).ints()[0].object.material,
+            Material::default()
+        );
+
+        let inner = Rc::new(Object::instance(
+            identity(),
+            sphere.clone(),
+            Some(red.clone()),
+        ));
+        let outer = Rc::new(Object::instance(
+            identity(),
+            inner.clone(),
+            Some(blue.clone()),
+        ));
+        assert_eq!(outer.intersections(&r).ints()[0].object.material, red);
+
+        let inner = Rc::new(Object::instance(translation(0., 0., 1.), sphere, None));
+        let outer = Rc::new(Object::instance(
+            scaling(2., 2., 2.),
+            inner,
+            Some(blue.clone()),
+        ));
+        let xs = outer.clone().intersections(&r);
+        assert_eq!(xs.ints()[0].object.material, blue);
+        assert_abs_diff_eq!(xs.ints()[0].t, 5.);
+        assert!(outer.occludes(&r, 10., &|m: &Material| *m == blue));
+        assert!(!outer.occludes(&r, 10., &|m: &Material| *m == red));
+    }
+
+    #[test]
+    fn children_inherit_group_material_unless_they_override() {
+        let red = MaterialBuilder::default()
+            .color(Color::new(1., 0., 0.))
+            .build();
+        let blue = MaterialBuilder::default()
+            .color(Color::new(0., 0., 1.))
+            .build();
+
+        let mut g = Object::group_with_material(
+            identity(),
+            red.clone(),
+            vec![
+                Rc::new(default_sphere().inheriting()),
+                Rc::new(Object::sphere(identity(), blue.clone())),
+                Object::group_with_material(
+                    identity(),
+                    Material::default(),
+                    vec![Rc::new(default_cube().inheriting())],
+                ),
+                Object::group(identity(), vec![Rc::new(default_cube().inheriting())]),
+            ],
+        );
+
+        let materials = |g: &Object| {
+            [
+                g.children()[0].material.clone(),
+                g.children()[1].material.clone(),
+                g.children()[2].children()[0].material.clone(),
+                g.children()[3].children()[0].material.clone(),
+            ]
+        };
+        assert_eq!(
+            materials(&g),
+            [red.clone(), blue.clone(), Material::default(), red.clone()]
+        );
+        assert!(g.children()[3].inherits_material());
+        assert!(!g.children()[2].inherits_material());
+
+        Rc::make_mut(&mut g).set_material(blue.clone());
+        assert_eq!(
+            materials(&g),
+            [
+                blue.clone(),
+                blue.clone(),
+                Material::default(),
+                blue.clone()
+            ]
+        );
+
+        // a child given its own material stops inheriting
+        Rc::make_mut(&mut g).set_material(red.clone());
+        let removed = Rc::make_mut(&mut g).remove_child(0).unwrap();
+        assert_eq!(removed.material, red);
+        assert!(removed.inherits_material());
+        let mut removed = Rc::unwrap_or_clone(removed);
+        removed.set_material(blue.clone());
+        assert!(!removed.inherits_material());
+    }
+
+    #[test]
+    fn group_normals_come_from_the_nearest_child() {
+        let g = Object::group(
+            translation(0., 1., 0.),
+            vec![
+                Rc::new(Object::sphere(
+                    translation(-2., 0., 0.),
+                    Material::default(),
+                )),
+                Rc::new(Object::cube(translation(2., 0., 0.), Material::default())),
+            ],
+        );
+
+        assert_abs_diff_eq!(
+            g.normal_at(Point::point(-2., 2., 0.)),
+            Vector::vector(0., 1., 0.)
+        );
+        assert_abs_diff_eq!(
+            g.normal_at(Point::point(3., 1.5, 0.5)),
+            Vector::vector(1., 0., 0.)
+        );
+        assert_eq!(
+            g.nearest_surface(Point::point(2.5, 1., 0.))
+                .unwrap()
+                .obj_type
+                .name(),
+            "cube"
+        );
+
+        let instance = Object::instance(rotation(RotationAxis::Z, FRAC_PI_2), g, None);
+        assert_abs_diff_eq!(
+            instance.normal_at(Point::point(-2., -2., 0.)),
+            Vector::vector(-1., 0., 0.)
+        );
+
+        let empty = Object::group(identity(), vec![]);
+        assert_eq!(empty.normal_at(Point::origin()), Vector::vector(0., 0., 0.));
+    }
+
+    #[test]
+    fn nearest_points_on_surfaces() {
+        let cases = [
+            (
+                default_cube(),
+                Point::point(0.5, 0.2, -0.9),
+                Point::point(0.5, 0.2, -1.),
+            ),
+            (
+                default_cube(),
+                Point::point(3., 0.5, 2.),
+                Point::point(1., 0.5, 1.),
+            ),
+            (
+                Object::cylinder(identity(), Material::default(), 0., 1., true),
+                Point::point(0.2, 5., 0.),
+                Point::point(0.2, 1., 0.),
+            ),
+            (
+                Object::cylinder(identity(), Material::default(), 0., 1., false),
+                Point::point(0.2, 5., 0.),
+                Point::point(1., 1., 0.),
+            ),
+            (
+                Object::cone(identity(), Material::default(), -1., 1., false),
+                Point::point(0., 1., 1.),
+                Point::point(0., 1., 1.),
+            ),
+            (
+                Object::cone(identity(), Material::default(), -1., 1., false),
+                Point::point(2., 0., 0.),
+                Point::point(1., 1., 0.),
+            ),
+            (
+                basic_triangle(
+                    Point::point(0., 1., 0.),
+                    Point::point(-1., 0., 0.),
+                    Point::point(1., 0., 0.),
+                ),
+                Point::point(0., 0.5, 3.),
+                Point::point(0., 0.5, 0.),
+            ),
+            (
+                basic_triangle(
+                    Point::point(0., 1., 0.),
+                    Point::point(-1., 0., 0.),
+                    Point::point(1., 0., 0.),
+                ),
+                Point::point(3., -1., 0.),
+                Point::point(1., 0., 0.),
+            ),
+        ];
+
+        for (shape, p, expected) in cases {
+            assert_abs_diff_eq!(shape.local_surface_point(p), expected);
+        }
+    }
+
+    #[test]
+    fn ray_hits_torus() {
+        let torus = Rc::new(Object::torus(identity(), Material::default(), 1., 0.25));
+        let cases = [
+            (
+                Point::point(-5., 0., 0.),
+                Vector::vector(1., 0., 0.),
+                vec![3.75, 4.25, 5.75, 6.25],
+            ),
+            (
+                Point::point(1., 5., 0.),
+                Vector::vector(0., -1., 0.),
+                vec![4.75, 5.25],
+            ),
+            (
+                Point::point(0., 5., 0.),
+                Vector::vector(0., -1., 0.),
+                vec![],
+            ),
+            (
+                Point::point(-5., 0.5, 0.),
+                Vector::vector(1., 0., 0.),
+                vec![],
+            ),
+            // far from the origin, where a naive quartic loses its precision
+            (
+                Point::point(0., 0., -1e4),
+                Vector::vector(0., 0., 1.),
+                vec![1e4 - 1.25, 1e4 - 0.75, 1e4 + 0.75, 1e4 + 1.25],
+            ),
+        ];
+
+        for (origin, direction, expected) in cases {
+            let xs = torus.clone().intersections(&Ray::new(origin, direction));
+            let ts: Vec<_> = xs.ints().iter().map(|i| i.t).collect();
+
+            assert_eq!(ts.len(), expected.len(), "{:?}", ts);
+            for (t, e) in ts.iter().zip(expected) {
+                assert_abs_diff_eq!(*t, e, epsilon = 1e-6);
+            }
+        }
+
+        // scaling shrinks the local direction, which mustn't make the quartic look degenerate
+        let scaled = Rc::new(Object::torus(
+            scaling(300., 300., 300.),
+            Material::default(),
+            1.,
+            0.25,
+        ));
+        let xs = scaled.intersections(&Ray::new(
+            Point::point(-1500., 0., 0.),
+            Vector::vector(1., 0., 0.),
+        ));
+        let ts: Vec<_> = xs.ints().iter().map(|i| i.t).collect();
+        assert_eq!(ts.len(), 4, "{:?}", ts);
+        for (t, e) in ts.iter().zip([1125., 1275., 1725., 1875.]) {
+            assert_abs_diff_eq!(*t, e, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn oblique_rays_hit_torus_on_its_surface() {
+        let (major, minor) = (1., 0.25);
+        let torus = Rc::new(Object::torus(identity(), Material::default(), major, minor));
+        let on_torus = |p: &Point| {
+            let (x, y, z) = (p.x(), p.y(), p.z());
+            let s = x * x + y * y + z * z + major * major - minor * minor;
+            s * s - 4. * major * major * (x * x + z * z)
+        };
+
+        for (origin, around, across) in [
+            (Point::point(-3., 0.7, -2.), 0.3, 1.1),
+            (Point::point(2., -1.5, 0.4), 2.5, -0.6),
+            (Point::point(0.2, 3., 0.1), 4.0, 1.9),
+            (Point::point(-0.3, -0.2, 0.4), 5.2, 3.0),
+        ] {
+            // aims at a known point on the surface, reached at t = 1
+            let ring = major + minor * RayTracerFloat::cos(across);
+            let target = Point::point(
+                ring * RayTracerFloat::cos(around),
+                minor * RayTracerFloat::sin(across),
+                ring * RayTracerFloat::sin(around),
+            );
+            let ray = Ray::new(origin, target - origin);
+            let xs = torus.clone().intersections(&ray);
+
+            assert!(xs.ints().iter().any(|i| (i.t - 1.).abs() < 1e-9));
+            for i in xs.ints() {
+                assert_abs_diff_eq!(on_torus(&ray.position(i.t)), 0., epsilon = 1e-9);
+            }
+        }
+    }
+
+    #[test]
+    fn torus_normals_and_bounds() {
+        let torus = Object::torus(identity(), Material::default(), 1., 0.25);
+
+        for (p, n) in [
+            (Point::point(1.25, 0., 0.), Vector::vector(1., 0., 0.)),
+            (Point::point(0.75, 0., 0.), Vector::vector(-1., 0., 0.)),
+            (Point::point(1., 0.25, 0.), Vector::vector(0., 1., 0.)),
+            (Point::point(0., 0., -0.75), Vector::vector(0., 0., 1.)),
+        ] {
+            assert_abs_diff_eq!(torus.normal_at(p), n);
+        }
+
+        assert_eq!(
+            torus.bounds(),
+            Bounds::new(
+                Point::point(-1.25, -0.25, -1.25),
+                Point::point(1.25, 0.25, 1.25)
+            )
+        );
+        assert_abs_diff_eq!(
+            torus.local_surface_point(Point::point(0., 1., 2.)),
+            Point::point(0., 0.25 / 2f64.sqrt(), 1. + 0.25 / 2f64.sqrt())
+        );
+        assert_eq!(
+            Object::try_torus(identity(), Material::default(), 1., 0.),
+            Err(Error::InvalidRadius(0.))
+        );
+    }
+
+    #[test]
+    fn group_bounds_contain_their_children() {
+        let g = Object::group(
+            scaling(2., 2., 2.),
+            vec![
+                Rc::new(Object::sphere(translation(2., 0., 0.), Material::default())),
+                Rc::new(Object::torus(
+                    translation(0., 0., -3.),
+                    Material::default(),
+                    1.,
+                    0.5,
+                )),
+            ],
+        );
+
+        assert_eq!(
+            g.bounds(),
+            Bounds::new(Point::point(-1.5, -1., -4.5), Point::point(3., 1., 1.))
+        );
+        assert_eq!(
+            g.bounds().transform(g.transform()),
+            Bounds::new(Point::point(-3., -2., -9.), Point::point(6., 2., 2.))
+        );
+    }
+
+    #[test]
+    fn rays_hit_flat_shapes_only_within_their_edges() {
+        let down = |x, z| Ray::new(Point::point(x, 2., z), Vector::vector(0., -1., 0.));
+        let disk = Rc::new(Object::disk(identity(), Material::default()));
+        let rectangle = Rc::new(Object::rectangle(identity(), Material::default()));
+        let annulus = Rc::new(Object::annulus(identity(), Material::default(), 0.5));
+
+        for (shape, x, z, hits) in [
+            (&disk, 0., 0., true),
+            (&disk, 0.7, 0.7, true),
+            (&disk, 0.8, 0.8, false),
+            (&rectangle, 0.9, -0.9, true),
+            (&rectangle, 1.1, 0., false),
+            (&annulus, 0.2, 0., false),
+            (&annulus, 0., 0.75, true),
+            (&annulus, 1.01, 0., false),
+        ] {
+            let xs = shape.clone().intersections(&down(x, z));
+            assert_eq!(xs.ints().len(), usize::from(hits), "({}, {})", x, z);
+            if hits {
+                assert_eq!(xs.ints()[0].t, 2.);
+            }
+        }
+
+        // parallel rays pass by, even in the plane of the shape
+        let along = Ray::new(Point::point(-5., 0., 0.), Vector::vector(1., 0., 0.));
+        assert!(disk.clone().intersections(&along).ints().is_empty());
+
+        let tilted = Rc::new(Object::disk(
+            translation(0., 0., 5.) * rotation(RotationAxis::X, FRAC_PI_2),
+            Material::default(),
+        ));
+        let ahead = Ray::new(Point::origin(), Vector::vector(0., 0., 1.));
+        assert_eq!(tilted.clone().intersections(&ahead).ints()[0].t, 5.);
+        assert_abs_diff_eq!(
+            tilted.normal_at(Point::point(0.5, 0., 5.)),
+            Vector::vector(0., 0., 1.)
+        );
+    }
+
+    #[test]
+    fn flat_shapes_sample_points_on_themselves() {
+        let annulus = Object::annulus(translation(0., 3., 0.), Material::default(), 0.5);
+        let rectangle = Object::rectangle(scaling(2., 1., 3.), Material::default());
+
+        for u in [0., 0.25, 0.5, 0.999] {
+            for v in [0., 0.3, 0.6, 0.999] {
+                let p = annulus.surface_point(u, v).unwrap();
+                let r = p.x().hypot(p.z());
+                assert_abs_diff_eq!(p.y(), 3.);
+                assert!((0.5 - EPSILON..=1. + EPSILON).contains(&r), "{}", r);
+
+                let p = rectangle.surface_point(u, v).unwrap();
+                assert!(p.x().abs() <= 2. && p.z().abs() <= 3. && p.y() == 0.);
+            }
+        }
+
+        assert_eq!(default_sphere().surface_point(0.5, 0.5), None);
+        assert_eq!(
+            Object::try_annulus(identity(), Material::default(), 1.),
+            Err(Error::InvalidRadius(1.))
+        );
+        assert_abs_diff_eq!(
+            annulus.local_surface_point(Point::point(0.1, 1., 0.)),
+            Point::point(0.5, 0., 0.)
+        );
+    }
 }
@@ -1,53 +1,297 @@
 use std::fmt::Debug;
 
-use crate::{color::Color, objects::Object, transforms::Transform, tuple::Point};
-
-#[derive(Debug, PartialEq, Copy, Clone)]
+use crate::{
+    color::Color,
+    error::Result,
+    noise::Fractal,
+    objects::Object,
+    transforms::{InvertibleTransform, Transform},
+    tuple::Point,
+    util::RayTracerFloat,
+};
+
+pub mod texture;
+pub mod uv;
+
+use self::uv::{CubeFace, UvMapping, UvPattern};
+
+/// Patterns that split space into regions (stripes, rings, checkers, gradients, blends) fill
+/// each region with a sub-pattern. A sub-pattern is evaluated at the point in its parent's
+/// pattern space and then applies its own transform, so nested transforms compose.
+#[derive(Debug, PartialEq, Clone)]
 pub enum Pattern {
     // TODO cfg[test]
-    Test(Transform),
+    Test(InvertibleTransform),
     Stripe {
-        transform: Transform,
-        even: Color,
-        odd: Color,
+        transform: InvertibleTransform,
+        even: Box<Pattern>,
+        odd: Box<Pattern>,
     },
     Ring {
-        transform: Transform,
-        even: Color,
-        odd: Color,
+        transform: InvertibleTransform,
+        even: Box<Pattern>,
+        odd: Box<Pattern>,
     },
     Solid(Color),
     Gradient {
-        transform: Transform,
-        start: Color,
-        end: Color,
+        transform: InvertibleTransform,
+        start: Box<Pattern>,
+        end: Box<Pattern>,
+    },
+    /// a gradient that repeats outwards from the y-axis, like `Ring`
+    RadialGradient {
+        transform: InvertibleTransform,
+        start: Box<Pattern>,
+        end: Box<Pattern>,
     },
     Checker {
-        transform: Transform,
-        even: Color,
-        odd: Color,
+        transform: InvertibleTransform,
+        even: Box<Pattern>,
+        odd: Box<Pattern>,
     },
+    /// `first` and `second` mixed everywhere; a weight of 0 is all `first`, 1 all `second`
+    Blend {
+        transform: InvertibleTransform,
+        first: Box<Pattern>,
+        second: Box<Pattern>,
+        weight: RayTracerFloat,
+    },
+    /// a 2D pattern wrapped onto the surface
+    UvMap {
+        transform: InvertibleTransform,
+        mapping: UvMapping,
+        pattern: UvPattern,
+    },
+    /// a separate 2D pattern on each face of a cube, indexed in `CubeFace` order
+    CubeMap {
+        transform: InvertibleTransform,
+        faces: Box<[UvPattern; 6]>,
+    },
+    /// another pattern looked up at a point displaced by noise, `amount` units at most
+    Perturbed {
+        transform: InvertibleTransform,
+        pattern: Box<Pattern>,
+        amount: RayTracerFloat,
+        fractal: Fractal,
+    },
+}
+
+impl From<Color> for Pattern {
+    fn from(c: Color) -> Self {
+        Pattern::Solid(c)
+    }
 }
 
 impl Pattern {
-    fn transform(&self) -> Option<&Transform> {
+    pub fn test(transform: Transform) -> Self {
+        Self::try_test(transform).unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_test(transform: Transform) -> Result<Self> {
+        Ok(Pattern::Test(InvertibleTransform::new(transform)?))
+    }
+
+    pub fn stripe(transform: Transform, even: impl Into<Pattern>, odd: impl Into<Pattern>) -> Self {
+        Self::try_stripe(transform, even, odd)
+            .unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_stripe(
+        transform: Transform,
+        even: impl Into<Pattern>,
+        odd: impl Into<Pattern>,
+    ) -> Result<Self> {
+        Ok(Pattern::Stripe {
+            transform: InvertibleTransform::new(transform)?,
+            even: Box::new(even.into()),
+            odd: Box::new(odd.into()),
+        })
+    }
+
+    pub fn ring(transform: Transform, even: impl Into<Pattern>, odd: impl Into<Pattern>) -> Self {
+        Self::try_ring(transform, even, odd)
+            .unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_ring(
+        transform: Transform,
+        even: impl Into<Pattern>,
+        odd: impl Into<Pattern>,
+    ) -> Result<Self> {
+        Ok(Pattern::Ring {
+            transform: InvertibleTransform::new(transform)?,
+            even: Box::new(even.into()),
+            odd: Box::new(odd.into()),
+        })
+    }
+
+    pub fn gradient(
+        transform: Transform,
+        start: impl Into<Pattern>,
+        end: impl Into<Pattern>,
+    ) -> Self {
+        Self::try_gradient(transform, start, end)
+            .unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_gradient(
+        transform: Transform,
+        start: impl Into<Pattern>,
+        end: impl Into<Pattern>,
+    ) -> Result<Self> {
+        Ok(Pattern::Gradient {
+            transform: InvertibleTransform::new(transform)?,
+            start: Box::new(start.into()),
+            end: Box::new(end.into()),
+        })
+    }
+
+    pub fn radial_gradient(
+        transform: Transform,
+        start: impl Into<Pattern>,
+        end: impl Into<Pattern>,
+    ) -> Self {
+        Self::try_radial_gradient(transform, start, end)
+            .unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_radial_gradient(
+        transform: Transform,
+        start: impl Into<Pattern>,
+        end: impl Into<Pattern>,
+    ) -> Result<Self> {
+        Ok(Pattern::RadialGradient {
+            transform: InvertibleTransform::new(transform)?,
+            start: Box::new(start.into()),
+            end: Box::new(end.into()),
+        })
+    }
+
+    pub fn checker(
+        transform: Transform,
+        even: impl Into<Pattern>,
+        odd: impl Into<Pattern>,
+    ) -> Self {
+        Self::try_checker(transform, even, odd)
+            .unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_checker(
+        transform: Transform,
+        even: impl Into<Pattern>,
+        odd: impl Into<Pattern>,
+    ) -> Result<Self> {
+        Ok(Pattern::Checker {
+            transform: InvertibleTransform::new(transform)?,
+            even: Box::new(even.into()),
+            odd: Box::new(odd.into()),
+        })
+    }
+
+    pub fn blend(
+        transform: Transform,
+        first: impl Into<Pattern>,
+        second: impl Into<Pattern>,
+        weight: RayTracerFloat,
+    ) -> Self {
+        Self::try_blend(transform, first, second, weight)
+            .unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_blend(
+        transform: Transform,
+        first: impl Into<Pattern>,
+        second: impl Into<Pattern>,
+        weight: RayTracerFloat,
+    ) -> Result<Self> {
+        Ok(Pattern::Blend {
+            transform: InvertibleTransform::new(transform)?,
+            first: Box::new(first.into()),
+            second: Box::new(second.into()),
+            weight,
+        })
+    }
+
+    pub fn uv_map(transform: Transform, mapping: UvMapping, pattern: UvPattern) -> Self {
+        Self::try_uv_map(transform, mapping, pattern)
+            .unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_uv_map(
+        transform: Transform,
+        mapping: UvMapping,
+        pattern: UvPattern,
+    ) -> Result<Self> {
+        Ok(Pattern::UvMap {
+            transform: InvertibleTransform::new(transform)?,
+            mapping,
+            pattern,
+        })
+    }
+
+    pub fn cube_map(transform: Transform, faces: [UvPattern; 6]) -> Self {
+        Self::try_cube_map(transform, faces)
+            .unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_cube_map(transform: Transform, faces: [UvPattern; 6]) -> Result<Self> {
+        Ok(Pattern::CubeMap {
+            transform: InvertibleTransform::new(transform)?,
+            faces: Box::new(faces),
+        })
+    }
+
+    pub fn perturbed(
+        transform: Transform,
+        pattern: Pattern,
+        amount: RayTracerFloat,
+        fractal: Fractal,
+    ) -> Self {
+        Self::try_perturbed(transform, pattern, amount, fractal)
+            .unwrap_or_else(|e| panic!("cannot create pattern: {}", e))
+    }
+
+    pub fn try_perturbed(
+        transform: Transform,
+        pattern: Pattern,
+        amount: RayTracerFloat,
+        fractal: Fractal,
+    ) -> Result<Self> {
+        Ok(Pattern::Perturbed {
+            transform: InvertibleTransform::new(transform)?,
+            pattern: Box::new(pattern),
+            amount,
+            fractal,
+        })
+    }
+
+    pub fn transform(&self) -> Option<&InvertibleTransform> {
         match self {
             Pattern::Test(t) => Some(t),
             Pattern::Stripe { transform, .. }
             | Pattern::Ring { transform, .. }
             | Pattern::Gradient { transform, .. }
-            | Pattern::Checker { transform, .. } => Some(transform),
+            | Pattern::RadialGradient { transform, .. }
+            | Pattern::Checker { transform, .. }
+            | Pattern::Blend { transform, .. }
+            | Pattern::UvMap { transform, .. }
+            | Pattern::CubeMap { transform, .. }
+            | Pattern::Perturbed { transform, .. } => Some(transform),
             _ => None,
         }
     }
 
     pub fn color_at(&self, object: &Object, world_point: &Point) -> Color {
-        let object_point = object.world_point_to_local(*world_point);
+        self.local_color_at(&object.world_point_to_local(*world_point))
+    }
 
+    /// The color at a point in the parent pattern's space, or object space for a top-level
+    /// pattern, i.e. before this pattern's own transform
+    fn local_color_at(&self, point: &Point) -> Color {
         let p = if let Some(t) = self.transform() {
-            object_point.transform(&t.try_inverse().unwrap())
+            point.transform(t.inverse())
         } else {
-            object_point
+            *point
         };
 
         match self {
@@ -63,9 +307,9 @@ impl Pattern {
                 odd,
             } => {
                 if p.x().floor() % 2. == 0. {
-                    *even
+                    even.local_color_at(&p)
                 } else {
-                    *odd
+                    odd.local_color_at(&p)
                 }
             }
             Pattern::Ring {
@@ -75,17 +319,25 @@ impl Pattern {
             } => {
                 let dist_from_origin = (p.x().powi(2) + p.z().powi(2)).sqrt();
 
-                if dist_from_origin % 2. == 0. {
-                    *even
+                if dist_from_origin.floor() % 2. == 0. {
+                    even.local_color_at(&p)
                 } else {
-                    *odd
+                    odd.local_color_at(&p)
                 }
             }
             Pattern::Gradient {
                 transform: _,
                 start,
                 end,
-            } => *start + (*end - *start) * (p.x() - p.x().floor()),
+            } => lerp(start, end, &p, p.x() - p.x().floor()),
+            Pattern::RadialGradient {
+                transform: _,
+                start,
+                end,
+            } => {
+                let dist_from_origin = (p.x().powi(2) + p.z().powi(2)).sqrt();
+                lerp(start, end, &p, dist_from_origin - dist_from_origin.floor())
+            }
             Pattern::Checker {
                 transform: _,
                 even,
@@ -94,38 +346,86 @@ impl Pattern {
                 let floor_sum = p.x().floor() + p.y().floor() + p.z().floor();
 
                 if floor_sum % 2. == 0. {
-                    *even
+                    even.local_color_at(&p)
                 } else {
-                    *odd
+                    odd.local_color_at(&p)
                 }
             }
+            Pattern::Blend {
+                transform: _,
+                first,
+                second,
+                weight,
+            } => lerp(first, second, &p, *weight),
+            Pattern::UvMap {
+                transform: _,
+                mapping,
+                pattern,
+            } => {
+                let (u, v) = mapping.map(&p);
+                pattern.color_at(u, v)
+            }
+            Pattern::CubeMap {
+                transform: _,
+                faces,
+            } => {
+                let face = CubeFace::of(&p);
+                let (u, v) = face.uv(&p);
+                faces[face as usize].color_at(u, v)
+            }
+            Pattern::Perturbed {
+                transform: _,
+                pattern,
+                amount,
+                fractal,
+            } => {
+                // the inner pattern sees the jittered point in our space, then applies its own
+                // transform on top
+                let jittered = p + fractal.displacement(&p) * *amount;
+                pattern.local_color_at(&jittered)
+            }
         }
     }
 }
 
+/// Blends from `start` (t = 0) to `end` (t = 1), evaluating both at `p`
+fn lerp(start: &Pattern, end: &Pattern, p: &Point, t: RayTracerFloat) -> Color {
+    let start = start.local_color_at(p);
+    start + (end.local_color_at(p) - start) * t
+}
+
 #[cfg(test)]
 pub(crate) mod test {
+    use std::{f64::consts::FRAC_PI_2, rc::Rc};
+
     use crate::{
+        canvas::Canvas,
         color::{Color, BLACK, WHITE},
+        error::Error,
         material::{Material, MaterialBuilder},
+        noise::Fractal,
         objects::{default_sphere, Object},
-        transforms::{identity, scaling, translation},
+        transforms::{identity, scaling, translation, TransformBuilder},
         tuple::Point,
     };
 
-    use super::Pattern;
+    use super::{
+        texture::{Filter, Texture, Wrap},
+        uv::{UvMapping, UvPattern},
+        Pattern,
+    };
 
     pub fn default_test_pattern() -> Pattern {
-        Pattern::Test(identity())
+        Pattern::test(identity())
     }
 
     #[test]
     fn pattern_with_object_transform() {
-        let p = Pattern::Test(identity());
+        let p = Pattern::test(identity());
 
         let s = Object::sphere(
             scaling(2., 2., 2.),
-            MaterialBuilder::default().pattern(p).build(),
+            MaterialBuilder::default().pattern(p.clone()).build(),
         );
 
         let c = p.color_at(&s, &Point::point(2., 3., 4.));
@@ -135,9 +435,12 @@ pub(crate) mod test {
 
     #[test]
     fn pattern_with_pattern_transform() {
-        let p = Pattern::Test(scaling(2., 2., 2.));
+        let p = Pattern::test(scaling(2., 2., 2.));
 
-        let s = Object::sphere(identity(), MaterialBuilder::default().pattern(p).build());
+        let s = Object::sphere(
+            identity(),
+            MaterialBuilder::default().pattern(p.clone()).build(),
+        );
 
         let c = p.color_at(&s, &Point::point(2., 3., 4.));
 
@@ -146,11 +449,11 @@ pub(crate) mod test {
 
     #[test]
     fn pattern_with_object_and_pattern_transform() {
-        let p = Pattern::Test(translation(0.5, 1., 1.5));
+        let p = Pattern::test(translation(0.5, 1., 1.5));
 
         let s = Object::sphere(
             scaling(2., 2., 2.),
-            MaterialBuilder::default().pattern(p).build(),
+            MaterialBuilder::default().pattern(p.clone()).build(),
         );
 
         let c = p.color_at(&s, &Point::point(2.5, 3., 3.5));
@@ -160,11 +463,7 @@ pub(crate) mod test {
 
     #[test]
     fn stripe_with_object_transform() {
-        let p = Pattern::Stripe {
-            even: WHITE,
-            odd: BLACK,
-            transform: identity(),
-        };
+        let p = Pattern::stripe(identity(), WHITE, BLACK);
         let s = Object::sphere(scaling(2., 2., 2.), Material::default());
         let point = Point::point(1.5, 0., 0.);
         let c = p.color_at(&s, &point);
@@ -173,11 +472,7 @@ pub(crate) mod test {
 
     #[test]
     fn stripe_with_pattern_transform() {
-        let p = Pattern::Stripe {
-            even: WHITE,
-            odd: BLACK,
-            transform: scaling(2., 2., 2.),
-        };
+        let p = Pattern::stripe(scaling(2., 2., 2.), WHITE, BLACK);
         let s = Object::sphere(identity(), Material::default());
         let point = Point::point(1.5, 0., 0.);
         let c = p.color_at(&s, &point);
@@ -186,11 +481,7 @@ pub(crate) mod test {
 
     #[test]
     fn stripe_with_pattern_and_obj_transform() {
-        let p = Pattern::Stripe {
-            even: WHITE,
-            odd: BLACK,
-            transform: translation(0.5, 0., 0.),
-        };
+        let p = Pattern::stripe(translation(0.5, 0., 0.), WHITE, BLACK);
         let s = Object::sphere(scaling(2., 2., 2.), Material::default());
         let point = Point::point(2.5, 0., 0.);
         let c = p.color_at(&s, &point);
@@ -199,11 +490,7 @@ pub(crate) mod test {
 
     #[test]
     fn ring_should_extend_in_both_x_and_z() {
-        let p = Pattern::Ring {
-            even: WHITE,
-            odd: BLACK,
-            transform: identity(),
-        };
+        let p = Pattern::ring(identity(), WHITE, BLACK);
 
         let s = default_sphere();
 
@@ -215,11 +502,7 @@ pub(crate) mod test {
 
     #[test]
     fn gradient_lerps_between_colors() {
-        let pattern = Pattern::Gradient {
-            start: WHITE,
-            end: BLACK,
-            transform: identity(),
-        };
+        let pattern = Pattern::gradient(identity(), WHITE, BLACK);
 
         let s = default_sphere();
 
@@ -240,11 +523,7 @@ pub(crate) mod test {
 
     #[test]
     fn checkers_should_repeat_in_each_dim() {
-        let p = Pattern::Checker {
-            even: WHITE,
-            odd: BLACK,
-            transform: identity(),
-        };
+        let p = Pattern::checker(identity(), WHITE, BLACK);
 
         let s = default_sphere();
 
@@ -258,4 +537,217 @@ pub(crate) mod test {
         assert_eq!(p.color_at(&s, &Point::point(0., 0., 0.99)), WHITE);
         assert_eq!(p.color_at(&s, &Point::point(0., 0., 1.01)), BLACK);
     }
+
+    #[test]
+    fn uv_checkers_wrap_around_sphere() {
+        let p = Pattern::uv_map(
+            identity(),
+            UvMapping::Spherical,
+            UvPattern::checkers(16., 8., BLACK, WHITE),
+        );
+
+        let s = default_sphere();
+
+        // the same point on the surface of a bigger sphere gets the same color
+        for (x, y, z) in [
+            (0.4315, 0.4670, 0.7719),
+            (-0.9654, 0.2552, -0.0534),
+            (0.1039, 0.7090, 0.6975),
+        ] {
+            assert_eq!(
+                p.color_at(&s, &Point::point(x, y, z)),
+                p.color_at(&s, &Point::point(2. * x, 2. * y, 2. * z))
+            );
+        }
+
+        assert_ne!(
+            p.color_at(&s, &Point::point(0., 0., -1.)),
+            p.color_at(&s, &Point::point(-0.3827, 0., -0.9239))
+        );
+    }
+
+    #[test]
+    fn cube_map_picks_face_pattern() {
+        let red = Color::new(1., 0., 0.);
+        let yellow = Color::new(1., 1., 0.);
+        let faces = [
+            UvPattern::align_check(yellow, red, red, red, red),
+            UvPattern::align_check(red, red, red, red, red),
+            UvPattern::align_check(WHITE, red, red, red, red),
+            UvPattern::align_check(BLACK, red, red, red, red),
+            UvPattern::align_check(WHITE, BLACK, BLACK, BLACK, BLACK),
+            UvPattern::align_check(BLACK, WHITE, WHITE, WHITE, WHITE),
+        ];
+
+        let p = Pattern::cube_map(identity(), faces);
+        let s = Object::cube(identity(), Material::default());
+
+        assert_eq!(p.color_at(&s, &Point::point(-1., 0., 0.)), yellow);
+        assert_eq!(p.color_at(&s, &Point::point(-1., 0.9, -0.9)), red);
+        assert_eq!(p.color_at(&s, &Point::point(1., 0., 0.)), WHITE);
+        assert_eq!(p.color_at(&s, &Point::point(0., 0., -1.)), BLACK);
+        assert_eq!(p.color_at(&s, &Point::point(0., 1., 0.)), WHITE);
+        assert_eq!(p.color_at(&s, &Point::point(-0.9, 1., -0.9)), BLACK);
+        assert_eq!(p.color_at(&s, &Point::point(0., -1., 0.)), BLACK);
+    }
+
+    #[test]
+    fn cube_map_of_images() {
+        let colors = [
+            Color::new(1., 0., 0.),
+            Color::new(0., 1., 0.),
+            Color::new(0., 0., 1.),
+            Color::new(1., 1., 0.),
+            Color::new(0., 1., 1.),
+            Color::new(1., 0., 1.),
+        ];
+
+        let faces = colors.map(|c| {
+            let mut image = Canvas::new(1, 1);
+            image.write(0, 0, c);
+            UvPattern::image(Texture::new(Rc::new(image), Filter::Nearest, Wrap::Clamp).unwrap())
+        });
+
+        let p = Pattern::cube_map(identity(), faces);
+        let s = Object::cube(identity(), Material::default());
+
+        for (point, color) in [
+            (Point::point(-1., 0.2, 0.3), colors[0]),
+            (Point::point(0.2, 0.3, 1.), colors[1]),
+            (Point::point(1., 0.2, 0.3), colors[2]),
+            (Point::point(0.2, 0.3, -1.), colors[3]),
+            (Point::point(0.2, 1., 0.3), colors[4]),
+            (Point::point(0.2, -1., 0.3), colors[5]),
+        ] {
+            assert_eq!(p.color_at(&s, &point), color);
+        }
+    }
+
+    #[test]
+    fn perturbed_with_no_amount_matches_inner() {
+        let inner = Pattern::stripe(scaling(0.25, 1., 1.), WHITE, BLACK);
+        let p = Pattern::perturbed(identity(), inner.clone(), 0., Fractal::Plain);
+        let s = default_sphere();
+
+        for x in [0.1, 0.3, 0.6, 0.9] {
+            let point = Point::point(x, 0.2, 0.4);
+            assert_eq!(p.color_at(&s, &point), inner.color_at(&s, &point));
+        }
+    }
+
+    #[test]
+    fn perturbed_stripes_wobble() {
+        // straight stripes are constant along y; perturbed ones aren't
+        let inner = Pattern::stripe(scaling(0.5, 1., 1.), WHITE, BLACK);
+        let marble = Pattern::perturbed(
+            identity(),
+            inner.clone(),
+            0.5,
+            Fractal::Turbulence { octaves: 4 },
+        );
+        let s = default_sphere();
+
+        let column = |p: &Pattern| {
+            (0..50)
+                .map(|i| p.color_at(&s, &Point::point(0.3, i as f64 * 0.13, 0.7)))
+                .collect::<Vec<_>>()
+        };
+
+        assert!(column(&inner).windows(2).all(|w| w[0] == w[1]));
+        assert!(column(&marble).windows(2).any(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn checker_of_stripes() {
+        let red = Color::new(1., 0., 0.);
+        let green = Color::new(0., 1., 0.);
+
+        // stripes half as wide as the checks, running along x in one check and z in the other
+        let p = Pattern::checker(
+            identity(),
+            Pattern::stripe(scaling(0.5, 1., 1.), WHITE, BLACK),
+            Pattern::stripe(
+                identity().scale_by(0.5, 1., 1.).rotate_y(-FRAC_PI_2),
+                red,
+                green,
+            ),
+        );
+        let s = default_sphere();
+
+        assert_eq!(p.color_at(&s, &Point::point(0.25, 0.5, 0.5)), WHITE);
+        assert_eq!(p.color_at(&s, &Point::point(0.75, 0.5, 0.5)), BLACK);
+        assert_eq!(p.color_at(&s, &Point::point(1.25, 0.5, 0.25)), red);
+        assert_eq!(p.color_at(&s, &Point::point(1.25, 0.5, 0.75)), green);
+    }
+
+    #[test]
+    fn nested_transforms_compose() {
+        // the outer translation applies to the inner stripe as well as its own
+        let p = Pattern::checker(
+            translation(0., 10., 0.),
+            Pattern::stripe(translation(0.5, 0., 0.), WHITE, BLACK),
+            BLACK,
+        );
+        let s = default_sphere();
+
+        assert_eq!(p.color_at(&s, &Point::point(0.6, 10.5, 0.5)), WHITE);
+        assert_eq!(p.color_at(&s, &Point::point(0.4, 10.5, 0.5)), BLACK);
+    }
+
+    #[test]
+    fn blend_averages_patterns() {
+        let p = Pattern::blend(
+            identity(),
+            Pattern::stripe(identity(), WHITE, BLACK),
+            Pattern::stripe(scaling(0.5, 1., 1.), WHITE, BLACK),
+            0.25,
+        );
+        let s = default_sphere();
+
+        assert_eq!(p.color_at(&s, &Point::point(0.25, 0., 0.)), WHITE);
+        assert_eq!(
+            p.color_at(&s, &Point::point(0.75, 0., 0.)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+        assert_eq!(
+            p.color_at(&s, &Point::point(1.75, 0., 0.)),
+            Color::new(0., 0., 0.)
+        );
+    }
+
+    #[test]
+    fn radial_gradient_repeats_outwards() {
+        let p = Pattern::radial_gradient(identity(), WHITE, BLACK);
+        let s = default_sphere();
+
+        assert_eq!(p.color_at(&s, &Point::point(0., 0., 0.)), WHITE);
+        assert_eq!(
+            p.color_at(&s, &Point::point(0.3, 0., 0.4)),
+            Color::new(0.5, 0.5, 0.5)
+        );
+        assert_eq!(
+            p.color_at(&s, &Point::point(0., 5., 1.25)),
+            Color::new(0.75, 0.75, 0.75)
+        );
+    }
+
+    #[test]
+    fn try_constructors_reject_singular_transforms() {
+        let flat = scaling(1., 0., 1.);
+
+        assert_eq!(
+            Pattern::try_stripe(flat, WHITE, BLACK),
+            Err(Error::NonInvertibleTransform)
+        );
+        assert_eq!(
+            Pattern::try_perturbed(
+                flat,
+                Pattern::Solid(WHITE),
+                0.1,
+                Fractal::Fbm { octaves: 2 }
+            ),
+            Err(Error::NonInvertibleTransform)
+        );
+        assert!(Pattern::try_checker(identity(), WHITE, BLACK).is_ok());
+    }
 }
@@ -0,0 +1,175 @@
+use std::{path::Path, rc::Rc};
+
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    error::{Error, Result},
+    util::RayTracerFloat,
+};
+
+/// How a texture is sampled between pixel centers
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum Filter {
+    #[default]
+    Nearest,
+    Bilinear,
+}
+
+/// What a texture does with coordinates outside [0, 1)
+#[derive(Debug, Default, PartialEq, Eq, Copy, Clone)]
+pub enum Wrap {
+    /// tile the image
+    #[default]
+    Repeat,
+    /// stretch the edge pixels outwards
+    Clamp,
+}
+
+/// An image sampled at texture coordinates; u runs left to right and v bottom to top
+#[derive(Debug, Clone)]
+pub struct Texture {
+    image: Rc<Canvas>,
+    pub filter: Filter,
+    pub wrap: Wrap,
+}
+
+/// Textures are equal when they sample the same image the same way
+impl PartialEq for Texture {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.image, &other.image)
+            && self.filter == other.filter
+            && self.wrap == other.wrap
+    }
+}
+
+impl Texture {
+    pub fn new(image: Rc<Canvas>, filter: Filter, wrap: Wrap) -> Result<Self> {
+        if image.width() == 0 || image.height() == 0 {
+            return Err(Error::InvalidDimensions {
+                width: image.width(),
+                height: image.height(),
+            });
+        }
+
+        Ok(Self {
+            image,
+            filter,
+            wrap,
+        })
+    }
+
+    pub fn load(path: &Path, filter: Filter, wrap: Wrap) -> Result<Self> {
+        Self::new(Rc::new(Canvas::load_ppm(path)?), filter, wrap)
+    }
+
+    pub fn image(&self) -> &Rc<Canvas> {
+        &self.image
+    }
+
+    pub fn color_at(&self, u: RayTracerFloat, v: RayTracerFloat) -> Color {
+        let width = self.image.width() as RayTracerFloat;
+        let height = self.image.height() as RayTracerFloat;
+
+        // pixel space, with pixel centers at integer + 0.5 and row 0 at the top
+        let x = u * width;
+        let y = (1. - v) * height;
+
+        match self.filter {
+            Filter::Nearest => self.pixel(x.floor(), y.floor()),
+            Filter::Bilinear => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (x0, y0) = (x.floor(), y.floor());
+                let (tx, ty) = (x - x0, y - y0);
+
+                let top = self.pixel(x0, y0) * (1. - tx) + self.pixel(x0 + 1., y0) * tx;
+                let bottom =
+                    self.pixel(x0, y0 + 1.) * (1. - tx) + self.pixel(x0 + 1., y0 + 1.) * tx;
+
+                top * (1. - ty) + bottom * ty
+            }
+        }
+    }
+
+    fn pixel(&self, x: RayTracerFloat, y: RayTracerFloat) -> Color {
+        let x = self.wrap.index(x, self.image.width());
+        let y = self.wrap.index(y, self.image.height());
+        self.image.pixel_at(x, y)
+    }
+}
+
+impl Wrap {
+    fn index(&self, i: RayTracerFloat, len: usize) -> usize {
+        let len = len as RayTracerFloat;
+
+        let i = match self {
+            Wrap::Repeat => i.rem_euclid(len),
+            Wrap::Clamp => i.clamp(0., len - 1.),
+        };
+
+        // rem_euclid can round up to len for tiny negative inputs
+        (i as usize).min(len as usize - 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::rc::Rc;
+
+    use approx::assert_abs_diff_eq;
+
+    use crate::{
+        canvas::Canvas,
+        color::{Color, BLACK, WHITE},
+    };
+
+    use super::{Filter, Texture, Wrap};
+
+    fn two_by_one() -> Rc<Canvas> {
+        let mut c = Canvas::new(2, 1);
+        c.write(0, 0, BLACK);
+        c.write(1, 0, WHITE);
+        Rc::new(c)
+    }
+
+    #[test]
+    fn nearest_picks_containing_pixel() {
+        let mut c = Canvas::new(2, 2);
+        c.write(0, 0, Color::new(1., 0., 0.));
+        c.write(1, 1, Color::new(0., 0., 1.));
+        let t = Texture::new(Rc::new(c), Filter::Nearest, Wrap::Repeat).unwrap();
+
+        // v = 1 is the top row of the image
+        assert_eq!(t.color_at(0.25, 0.75), Color::new(1., 0., 0.));
+        assert_eq!(t.color_at(0.75, 0.25), Color::new(0., 0., 1.));
+        assert_eq!(t.color_at(0.75, 0.75), BLACK);
+    }
+
+    #[test]
+    fn bilinear_blends_neighbors() {
+        let t = Texture::new(two_by_one(), Filter::Bilinear, Wrap::Clamp).unwrap();
+
+        assert_abs_diff_eq!(t.color_at(0.25, 0.5), BLACK);
+        assert_abs_diff_eq!(t.color_at(0.5, 0.5), Color::new(0.5, 0.5, 0.5));
+        assert_abs_diff_eq!(t.color_at(0.75, 0.5), WHITE);
+        assert_abs_diff_eq!(t.color_at(1.0, 0.5), WHITE);
+    }
+
+    #[test]
+    fn wrap_modes_at_edges() {
+        let repeat = Texture::new(two_by_one(), Filter::Bilinear, Wrap::Repeat).unwrap();
+        let clamp = Texture::new(two_by_one(), Filter::Bilinear, Wrap::Clamp).unwrap();
+
+        // repeating blends the right edge back into the left
+        assert_abs_diff_eq!(repeat.color_at(1.0, 0.5), Color::new(0.5, 0.5, 0.5));
+        assert_abs_diff_eq!(clamp.color_at(1.0, 0.5), WHITE);
+
+        let nearest = Texture::new(two_by_one(), Filter::Nearest, Wrap::Repeat).unwrap();
+        assert_eq!(nearest.color_at(1.25, 0.5), BLACK);
+        assert_eq!(nearest.color_at(-0.25, 0.5), WHITE);
+    }
+
+    #[test]
+    fn empty_image_rejected() {
+        assert!(Texture::new(Rc::new(Canvas::default()), Filter::Nearest, Wrap::Repeat).is_err());
+    }
+}
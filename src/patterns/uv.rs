@@ -0,0 +1,296 @@
+use std::f64::consts::TAU;
+
+use crate::{
+    color::Color,
+    patterns::texture::Texture,
+    tuple::Point,
+    util::{get_sphere_uv, RayTracerFloat},
+};
+
+/// A two-dimensional pattern, evaluated at texture coordinates in [0, 1)
+#[derive(Debug, PartialEq, Clone)]
+pub enum UvPattern {
+    Checkers {
+        width: RayTracerFloat,
+        height: RayTracerFloat,
+        even: Color,
+        odd: Color,
+    },
+    /// a solid color with distinct corners, for checking how a mapping is oriented
+    AlignCheck {
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    },
+    Image(Texture),
+}
+
+impl UvPattern {
+    pub fn checkers(
+        width: RayTracerFloat,
+        height: RayTracerFloat,
+        even: Color,
+        odd: Color,
+    ) -> Self {
+        UvPattern::Checkers {
+            width,
+            height,
+            even,
+            odd,
+        }
+    }
+
+    pub fn align_check(
+        main: Color,
+        upper_left: Color,
+        upper_right: Color,
+        bottom_left: Color,
+        bottom_right: Color,
+    ) -> Self {
+        UvPattern::AlignCheck {
+            main,
+            upper_left,
+            upper_right,
+            bottom_left,
+            bottom_right,
+        }
+    }
+
+    pub fn image(texture: Texture) -> Self {
+        UvPattern::Image(texture)
+    }
+
+    pub fn color_at(&self, u: RayTracerFloat, v: RayTracerFloat) -> Color {
+        match self {
+            UvPattern::Checkers {
+                width,
+                height,
+                even,
+                odd,
+            } => {
+                let floor_sum = (u * width).floor() + (v * height).floor();
+
+                if floor_sum % 2. == 0. {
+                    *even
+                } else {
+                    *odd
+                }
+            }
+            UvPattern::AlignCheck {
+                main,
+                upper_left,
+                upper_right,
+                bottom_left,
+                bottom_right,
+            } => match (u, v) {
+                (u, v) if u < 0.2 && v > 0.8 => *upper_left,
+                (u, v) if u > 0.8 && v > 0.8 => *upper_right,
+                (u, v) if u < 0.2 && v < 0.2 => *bottom_left,
+                (u, v) if u > 0.8 && v < 0.2 => *bottom_right,
+                _ => *main,
+            },
+            UvPattern::Image(texture) => texture.color_at(u, v),
+        }
+    }
+}
+
+/// How a point in pattern space is flattened to texture coordinates
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum UvMapping {
+    /// wraps around the origin; v runs from the south pole to the north pole
+    Spherical,
+    /// tiles the xz-plane once per unit
+    Planar,
+    /// wraps around the y-axis; v repeats once per unit of height
+    Cylindrical,
+    /// maps each face of the [-1, 1] cube onto the whole texture
+    Cube,
+}
+
+impl UvMapping {
+    pub fn map(&self, p: &Point) -> (RayTracerFloat, RayTracerFloat) {
+        match self {
+            UvMapping::Spherical => {
+                let radius = (*p - Point::origin()).magnitude();
+
+                if radius == 0. {
+                    (0., 0.)
+                } else {
+                    get_sphere_uv(Point::point(p.x() / radius, p.y() / radius, p.z() / radius))
+                }
+            }
+            UvMapping::Planar => (p.x().rem_euclid(1.), p.z().rem_euclid(1.)),
+            UvMapping::Cylindrical => (cylinder_u(p), p.y().rem_euclid(1.)),
+            UvMapping::Cube => CubeFace::of(p).uv(p),
+        }
+    }
+}
+
+fn cylinder_u(p: &Point) -> RayTracerFloat {
+    // theta runs from -π to π around the y-axis; shift and flip so u increases
+    // counterclockwise when viewed from above, starting at -z
+    let theta = p.x().atan2(p.z());
+    1. - (theta / TAU + 0.5)
+}
+
+/// The faces of a cube, in the order a `Pattern::CubeMap` expects its textures
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+pub enum CubeFace {
+    Left,
+    Front,
+    Right,
+    Back,
+    Up,
+    Down,
+}
+
+impl CubeFace {
+    /// The face whose axis dominates `p`
+    pub fn of(p: &Point) -> Self {
+        let coord = p.x().abs().max(p.y().abs()).max(p.z().abs());
+
+        match coord {
+            c if c == p.x() => CubeFace::Right,
+            c if c == -p.x() => CubeFace::Left,
+            c if c == p.y() => CubeFace::Up,
+            c if c == -p.y() => CubeFace::Down,
+            c if c == p.z() => CubeFace::Front,
+            _ => CubeFace::Back,
+        }
+    }
+
+    /// Texture coordinates of `p` on this face, as seen from outside the cube
+    pub fn uv(&self, p: &Point) -> (RayTracerFloat, RayTracerFloat) {
+        let wrap = |c: RayTracerFloat| c.rem_euclid(2.) / 2.;
+
+        match self {
+            CubeFace::Front => (wrap(p.x() + 1.), wrap(p.y() + 1.)),
+            CubeFace::Back => (wrap(1. - p.x()), wrap(p.y() + 1.)),
+            CubeFace::Left => (wrap(p.z() + 1.), wrap(p.y() + 1.)),
+            CubeFace::Right => (wrap(1. - p.z()), wrap(p.y() + 1.)),
+            CubeFace::Up => (wrap(p.x() + 1.), wrap(1. - p.z())),
+            CubeFace::Down => (wrap(p.x() + 1.), wrap(p.z() + 1.)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use approx::assert_abs_diff_eq;
+
+    use crate::{
+        color::{Color, BLACK, WHITE},
+        tuple::Point,
+    };
+
+    use super::{CubeFace, UvMapping, UvPattern};
+
+    #[test]
+    fn checkers_in_uv_space() {
+        let checkers = UvPattern::checkers(2., 2., BLACK, WHITE);
+
+        for (u, v, expected) in [
+            (0.0, 0.0, BLACK),
+            (0.5, 0.0, WHITE),
+            (0.0, 0.5, WHITE),
+            (0.5, 0.5, BLACK),
+            (1.0, 1.0, BLACK),
+        ] {
+            assert_eq!(checkers.color_at(u, v), expected);
+        }
+    }
+
+    #[test]
+    fn align_check_corners() {
+        let main = Color::new(1., 1., 1.);
+        let ul = Color::new(1., 0., 0.);
+        let ur = Color::new(1., 1., 0.);
+        let bl = Color::new(0., 1., 0.);
+        let br = Color::new(0., 1., 1.);
+        let pattern = UvPattern::align_check(main, ul, ur, bl, br);
+
+        assert_eq!(pattern.color_at(0.5, 0.5), main);
+        assert_eq!(pattern.color_at(0.1, 0.9), ul);
+        assert_eq!(pattern.color_at(0.9, 0.9), ur);
+        assert_eq!(pattern.color_at(0.1, 0.1), bl);
+        assert_eq!(pattern.color_at(0.9, 0.1), br);
+    }
+
+    #[test]
+    fn planar_mapping() {
+        for (p, u, v) in [
+            (Point::point(0.25, 0., 0.5), 0.25, 0.5),
+            (Point::point(0.25, 0., -0.25), 0.25, 0.75),
+            (Point::point(0.25, 0.5, -0.25), 0.25, 0.75),
+            (Point::point(1.25, 0., 0.5), 0.25, 0.5),
+            (Point::point(0.25, 0., -1.75), 0.25, 0.25),
+        ] {
+            let (actual_u, actual_v) = UvMapping::Planar.map(&p);
+            assert_abs_diff_eq!(actual_u, u);
+            assert_abs_diff_eq!(actual_v, v);
+        }
+    }
+
+    #[test]
+    fn cylindrical_mapping() {
+        for (p, u, v) in [
+            (Point::point(0., 0., -1.), 0.0, 0.0),
+            (Point::point(0., 0.5, -1.), 0.0, 0.5),
+            (Point::point(0., 1., -1.), 0.0, 0.0),
+            (Point::point(FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2), 0.125, 0.5),
+            (Point::point(1., 0.5, 0.), 0.25, 0.5),
+            (Point::point(FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2), 0.375, 0.5),
+            (Point::point(0., -0.25, 1.), 0.5, 0.75),
+            (Point::point(-1., 1.25, 0.), 0.75, 0.25),
+        ] {
+            let (actual_u, actual_v) = UvMapping::Cylindrical.map(&p);
+            assert_abs_diff_eq!(actual_u, u, epsilon = 1e-4);
+            assert_abs_diff_eq!(actual_v, v, epsilon = 1e-4);
+        }
+    }
+
+    #[test]
+    fn spherical_mapping_normalizes_point() {
+        let (u, v) = UvMapping::Spherical.map(&Point::point(0., 3., 0.));
+        assert_abs_diff_eq!(v, 1.);
+        assert!((0. ..=1.).contains(&u));
+
+        let (_, v) = UvMapping::Spherical.map(&Point::point(0., -0.5, 0.));
+        assert_abs_diff_eq!(v, 0.);
+    }
+
+    #[test]
+    fn cube_faces() {
+        for (p, face) in [
+            (Point::point(-1., 0.5, -0.25), CubeFace::Left),
+            (Point::point(1.1, -0.75, 0.8), CubeFace::Right),
+            (Point::point(0.1, 0.6, 0.9), CubeFace::Front),
+            (Point::point(-0.7, 0., -2.), CubeFace::Back),
+            (Point::point(0.5, 1., 0.9), CubeFace::Up),
+            (Point::point(-0.2, -1.3, 1.1), CubeFace::Down),
+        ] {
+            assert_eq!(CubeFace::of(&p), face);
+        }
+    }
+
+    #[test]
+    fn cube_face_uvs() {
+        for (p, face, u, v) in [
+            (Point::point(-0.5, 0.5, 1.), CubeFace::Front, 0.25, 0.75),
+            (Point::point(0.5, -0.5, 1.), CubeFace::Front, 0.75, 0.25),
+            (Point::point(0.5, 0.5, -1.), CubeFace::Back, 0.25, 0.75),
+            (Point::point(-1., 0.5, -0.5), CubeFace::Left, 0.25, 0.75),
+            (Point::point(1., 0.5, 0.5), CubeFace::Right, 0.25, 0.75),
+            (Point::point(-0.5, 1., -0.5), CubeFace::Up, 0.25, 0.75),
+            (Point::point(-0.5, -1., 0.5), CubeFace::Down, 0.25, 0.75),
+        ] {
+            let (actual_u, actual_v) = face.uv(&p);
+            assert_abs_diff_eq!(actual_u, u);
+            assert_abs_diff_eq!(actual_v, v);
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use std::rc::Rc;
 
 use crate::{
-    intersection::Intersection,
+    color::{Color, WHITE},
+    material::{Medium, CHANNEL_WAVELENGTHS},
     objects::Object,
     tuple::{Point, Vector},
     util::RayTracerFloat,
@@ -20,12 +21,20 @@ pub struct Precompute {
     pub under_point: Point,
     pub n1: RayTracerFloat,
     pub n2: RayTracerFloat,
+    /// the interior of the object the ray was travelling through when it reached the hit, if
+    /// any
+    pub medium: Option<Medium>,
+    /// how far the ray travelled through `medium` to reach the hit
+    pub travelled: RayTracerFloat,
+    /// the interior of the object the ray passes into beyond the hit, if any
+    pub next_medium: Option<Medium>,
 }
 
 impl Precompute {
     #[allow(clippy::too_many_arguments)]
     pub fn new(
-        i: Rc<Intersection>,
+        t: RayTracerFloat,
+        object: Rc<Object>,
         point: Point,
         eyev: Vector,
         normalv: Vector,
@@ -35,10 +44,13 @@ impl Precompute {
         under_point: Point,
         n1: RayTracerFloat,
         n2: RayTracerFloat,
+        medium: Option<Medium>,
+        travelled: RayTracerFloat,
+        next_medium: Option<Medium>,
     ) -> Self {
         Self {
-            t: i.t,
-            object: i.object.clone(),
+            t,
+            object,
             point,
             eyev,
             normalv,
@@ -48,14 +60,59 @@ impl Precompute {
             under_point,
             n1,
             n2,
+            medium,
+            travelled,
+            next_medium,
+        }
+    }
+
+    /// Whether either side of the hit bends different wavelengths differently
+    pub fn is_dispersive(&self) -> bool {
+        [self.medium, self.next_medium]
+            .into_iter()
+            .flatten()
+            .any(|m| m.dispersion != 0.)
+    }
+
+    /// The refractive indices either side of the hit for light of `wavelength` micrometres
+    pub fn indices_at(&self, wavelength: RayTracerFloat) -> (RayTracerFloat, RayTracerFloat) {
+        let index = |m: Option<Medium>| m.map_or(1.0, |m| m.refractive_at(wavelength));
+
+        (index(self.medium), index(self.next_medium))
+    }
+
+    /// The fraction of each channel that survives the trip through the medium to the hit
+    pub fn transmittance(&self) -> Color {
+        match &self.medium {
+            Some(m) => m.transmittance(self.travelled),
+            None => WHITE,
         }
     }
 
     pub fn schlick(&self) -> RayTracerFloat {
+        self.schlick_with(self.n1, self.n2)
+    }
+
+    /// Schlick's approximation per channel, each at its own wavelength when the hit is
+    /// dispersive
+    pub fn reflectance(&self) -> Color {
+        if self.is_dispersive() {
+            let [r, g, b] = CHANNEL_WAVELENGTHS.map(|w| {
+                let (n1, n2) = self.indices_at(w);
+                self.schlick_with(n1, n2)
+            });
+            Color::new(r, g, b)
+        } else {
+            let r = self.schlick();
+            Color::new(r, r, r)
+        }
+    }
+
+    fn schlick_with(&self, n1: RayTracerFloat, n2: RayTracerFloat) -> RayTracerFloat {
         let mut cos = self.eyev.dot(&self.normalv);
 
-        if self.n1 > self.n2 {
-            let n12 = self.n1 / self.n2;
+        if n1 > n2 {
+            let n12 = n1 / n2;
             let sin2_t = n12.powi(2) * (1.0 - cos.powi(2));
 
             if sin2_t > 1.0 {
@@ -65,7 +122,7 @@ impl Precompute {
             cos = (1.0 - sin2_t).sqrt();
         }
 
-        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        let r0 = ((n1 - n2) / (n1 + n2)).powi(2);
 
         r0 + (1.0 - r0) * (1. - cos).powi(5)
     }
@@ -78,6 +135,7 @@ mod test {
     use approx::assert_abs_diff_eq;
 
     use crate::{
+        color::WHITE,
         intersection::{Intersection, Intersections},
         ray::Ray,
         tuple::{Point, Vector},
@@ -129,4 +187,30 @@ mod test {
         let comps = xs.ints()[0].clone().precompute_with(&r, xs.into());
         assert_abs_diff_eq!(comps.schlick(), 0.48873, epsilon = EPSILON);
     }
+
+    #[test]
+    fn medium_and_distance_travelled() {
+        let shape = Rc::new(glass_sphere());
+
+        let r = Ray::new(Point::point(0., 0., -0.5), Vector::vector(0., 0., 2.));
+
+        let xs = Intersections::new(vec![
+            Intersection::new(-0.25, shape.clone()).into(),
+            Intersection::new(0.75, shape.clone()).into(),
+        ]);
+
+        let outside = xs.ints()[0].clone().precompute_with(&r, xs.into());
+        assert!(outside.medium.is_none());
+        assert_eq!(outside.transmittance(), WHITE);
+
+        let xs = Intersections::new(vec![
+            Intersection::new(-0.25, shape.clone()).into(),
+            Intersection::new(0.75, shape.clone()).into(),
+        ]);
+
+        // only the 1.5 units in front of the ray's origin count
+        let inside = xs.ints()[1].clone().precompute_with(&r, xs.into());
+        assert_eq!(inside.medium, Some(shape.material.medium()));
+        assert_abs_diff_eq!(inside.travelled, 1.5);
+    }
 }
@@ -0,0 +1,330 @@
+use std::{fmt::Display, ops::Mul};
+
+use approx::{abs_diff_eq, AbsDiffEq};
+use nalgebra::{Matrix3, Rotation3, Unit, UnitQuaternion, Vector3};
+
+use crate::{
+    error::{Error, Result},
+    transforms::{scaling, translation, RotationAxis, Transform},
+    tuple::Vector,
+    util::{RayTracerFloat, EPSILON},
+};
+
+/// A rotation stored as a unit quaternion
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Quaternion(UnitQuaternion<RayTracerFloat>);
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Display for Quaternion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.axis_angle() {
+            Some((axis, angle)) => f.write_fmt(format_args!(
+                "rotate({:.4}, {:.4}, {:.4}; {:.2}°)",
+                axis.x(),
+                axis.y(),
+                axis.z(),
+                angle.to_degrees()
+            )),
+            None => f.write_str("rotate(none)"),
+        }
+    }
+}
+
+impl AbsDiffEq for Quaternion {
+    type Epsilon = RayTracerFloat;
+
+    fn default_epsilon() -> Self::Epsilon {
+        EPSILON
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+        // q and -q represent the same rotation
+        abs_diff_eq!(self.0.coords, other.0.coords, epsilon = epsilon)
+            || abs_diff_eq!(self.0.coords, -other.0.coords, epsilon = epsilon)
+    }
+}
+
+/// Composes two rotations; `a * b` applies `b` first, then `a`, just like transforms
+impl Mul for Quaternion {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Quaternion(self.0 * rhs.0)
+    }
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Quaternion(UnitQuaternion::identity())
+    }
+
+    /// Rotation by `r` radians around `axis`, which need not be normalized; a zero axis
+    /// yields the identity rotation
+    pub fn from_axis_angle(axis: &Vector, r: RayTracerFloat) -> Self {
+        match Unit::try_new(Vector3::new(axis.x(), axis.y(), axis.z()), EPSILON) {
+            Some(unit_axis) => Quaternion(UnitQuaternion::from_axis_angle(&unit_axis, r)),
+            None => Self::identity(),
+        }
+    }
+
+    pub fn from_rotation(axis: RotationAxis, r: RayTracerFloat) -> Self {
+        let v = match axis {
+            RotationAxis::X => Vector::vector(1., 0., 0.),
+            RotationAxis::Y => Vector::vector(0., 1., 0.),
+            RotationAxis::Z => Vector::vector(0., 0., 1.),
+        };
+
+        Self::from_axis_angle(&v, r)
+    }
+
+    /// The (normalized) axis and angle in radians of this rotation, or None for the identity
+    pub fn axis_angle(&self) -> Option<(Vector, RayTracerFloat)> {
+        self.0
+            .axis_angle()
+            .map(|(axis, angle)| (Vector::vector(axis.x, axis.y, axis.z), angle))
+    }
+
+    pub fn inverse(&self) -> Self {
+        Quaternion(self.0.inverse())
+    }
+
+    /// Spherical linear interpolation from `self` (t = 0) to `other` (t = 1) along the
+    /// shortest arc
+    pub fn slerp(&self, other: &Quaternion, t: RayTracerFloat) -> Self {
+        // try_slerp only fails when the two are (nearly) the same rotation
+        Quaternion(
+            self.0
+                .try_slerp(&other.0, t, RayTracerFloat::EPSILON)
+                .unwrap_or_else(|| self.0.nlerp(&other.0, t)),
+        )
+    }
+
+    pub fn rotate(&self, v: &Vector) -> Vector {
+        let r = self.0.transform_vector(&Vector3::new(v.x(), v.y(), v.z()));
+        Vector::vector(r.x, r.y, r.z)
+    }
+
+    pub fn to_transform(&self) -> Transform {
+        self.0.to_homogeneous()
+    }
+}
+
+/// A transform split into scale, then rotation, then translation. Shearing and projection
+/// cannot be represented and are lost by `decompose`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Decomposition {
+    pub translation: Vector,
+    pub rotation: Quaternion,
+    pub scale: Vector,
+}
+
+impl Default for Decomposition {
+    fn default() -> Self {
+        Self {
+            translation: Vector::vector(0., 0., 0.),
+            rotation: Quaternion::identity(),
+            scale: Vector::vector(1., 1., 1.),
+        }
+    }
+}
+
+impl Display for Decomposition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "translate({:.4}, {:.4}, {:.4}) {} scale({:.4}, {:.4}, {:.4})",
+            self.translation.x(),
+            self.translation.y(),
+            self.translation.z(),
+            self.rotation,
+            self.scale.x(),
+            self.scale.y(),
+            self.scale.z(),
+        ))
+    }
+}
+
+impl AbsDiffEq for Decomposition {
+    type Epsilon = RayTracerFloat;
+
+    fn default_epsilon() -> Self::Epsilon {
+        EPSILON
+    }
+
+    fn abs_diff_eq(&self, other: &Self, epsilon: Self::Epsilon) -> bool {
+        abs_diff_eq!(self.translation, other.translation, epsilon = epsilon)
+            && abs_diff_eq!(self.rotation, other.rotation, epsilon = epsilon)
+            && abs_diff_eq!(self.scale, other.scale, epsilon = epsilon)
+    }
+}
+
+impl Decomposition {
+    pub fn new(translation: Vector, rotation: Quaternion, scale: Vector) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    /// Splits an affine transform into translation, rotation and (possibly negative) scale
+    pub fn decompose(t: &Transform) -> Result<Self> {
+        let translation = Vector::vector(t[(0, 3)], t[(1, 3)], t[(2, 3)]);
+
+        let basis: Matrix3<RayTracerFloat> = t.fixed_view::<3, 3>(0, 0).into();
+        let mut scale = Vector3::new(
+            basis.column(0).norm(),
+            basis.column(1).norm(),
+            basis.column(2).norm(),
+        );
+
+        if scale.iter().any(|s| *s < EPSILON) {
+            return Err(Error::NonInvertibleTransform);
+        }
+
+        // a reflection can't be expressed as a rotation, so fold it into the x scale
+        if basis.determinant() < 0. {
+            scale.x = -scale.x;
+        }
+
+        let mut rotation = basis;
+
+        for (i, s) in scale.iter().enumerate() {
+            rotation.column_mut(i).unscale_mut(*s);
+        }
+
+        Ok(Self {
+            translation,
+            rotation: Quaternion(UnitQuaternion::from_rotation_matrix(
+                &Rotation3::from_matrix(&rotation),
+            )),
+            scale: Vector::vector(scale.x, scale.y, scale.z),
+        })
+    }
+
+    pub fn to_transform(&self) -> Transform {
+        translation(
+            self.translation.x(),
+            self.translation.y(),
+            self.translation.z(),
+        ) * self.rotation.to_transform()
+            * scaling(self.scale.x(), self.scale.y(), self.scale.z())
+    }
+
+    /// Interpolates translation and scale linearly and rotation spherically
+    pub fn interpolate(&self, other: &Decomposition, t: RayTracerFloat) -> Self {
+        Self {
+            translation: self.translation + (other.translation - self.translation) * t,
+            rotation: self.rotation.slerp(&other.rotation, t),
+            scale: self.scale + (other.scale - self.scale) * t,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+
+    use approx::assert_abs_diff_eq;
+
+    use crate::{
+        error::Error,
+        transforms::{identity, rotation, scaling, RotationAxis, TransformBuilder},
+        tuple::Vector,
+    };
+
+    use super::{Decomposition, Quaternion};
+
+    #[test]
+    fn quaternion_matches_matrix_rotation() {
+        for axis in [RotationAxis::X, RotationAxis::Y, RotationAxis::Z] {
+            assert_abs_diff_eq!(
+                Quaternion::from_rotation(axis, FRAC_PI_4).to_transform(),
+                rotation(axis, FRAC_PI_4),
+                epsilon = 1e-10
+            );
+        }
+    }
+
+    #[test]
+    fn composing_quaternions_applies_right_first() {
+        let q = Quaternion::from_rotation(RotationAxis::Z, FRAC_PI_2)
+            * Quaternion::from_rotation(RotationAxis::X, FRAC_PI_2);
+
+        assert_abs_diff_eq!(
+            q.to_transform(),
+            identity().rotate_x(FRAC_PI_2).rotate_z(FRAC_PI_2),
+            epsilon = 1e-10
+        );
+    }
+
+    #[test]
+    fn slerp_halfway() {
+        let start = Quaternion::identity();
+        let end = Quaternion::from_rotation(RotationAxis::Y, FRAC_PI_2);
+
+        assert_abs_diff_eq!(
+            start.slerp(&end, 0.5),
+            Quaternion::from_rotation(RotationAxis::Y, FRAC_PI_4)
+        );
+        assert_abs_diff_eq!(start.slerp(&end, 0.), start);
+        assert_abs_diff_eq!(start.slerp(&end, 1.), end);
+        assert_abs_diff_eq!(end.slerp(&end, 0.3), end);
+    }
+
+    #[test]
+    fn decompose_round_trips() {
+        let t = identity()
+            .scale_by(2., 3., 4.)
+            .rotate_around(&Vector::vector(1., 2., 3.), PI / 3.)
+            .translate(-1., 5., 0.5);
+
+        let d = Decomposition::decompose(&t).unwrap();
+
+        assert_abs_diff_eq!(d.translation, Vector::vector(-1., 5., 0.5));
+        assert_abs_diff_eq!(d.scale, Vector::vector(2., 3., 4.));
+        assert_abs_diff_eq!(
+            d.rotation,
+            Quaternion::from_axis_angle(&Vector::vector(1., 2., 3.), PI / 3.)
+        );
+        assert_abs_diff_eq!(d.to_transform(), t, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn decompose_reflection() {
+        let t = scaling(-1., 1., 1.);
+        let d = Decomposition::decompose(&t).unwrap();
+        assert_abs_diff_eq!(d.to_transform(), t, epsilon = 1e-10);
+    }
+
+    #[test]
+    fn decompose_singular() {
+        assert_eq!(
+            Decomposition::decompose(&scaling(0., 1., 1.)),
+            Err(Error::NonInvertibleTransform)
+        );
+    }
+
+    #[test]
+    fn interpolate_decompositions() {
+        let a = Decomposition::default();
+        let b = Decomposition::new(
+            Vector::vector(2., 0., 0.),
+            Quaternion::from_rotation(RotationAxis::Z, FRAC_PI_2),
+            Vector::vector(3., 3., 3.),
+        );
+
+        let mid = a.interpolate(&b, 0.5);
+
+        assert_abs_diff_eq!(mid.translation, Vector::vector(1., 0., 0.));
+        assert_abs_diff_eq!(mid.scale, Vector::vector(2., 2., 2.));
+        assert_abs_diff_eq!(
+            mid.rotation,
+            Quaternion::from_rotation(RotationAxis::Z, FRAC_PI_4)
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use crate::error::{Error, Result};
 use crate::transforms::Transform;
 use crate::tuple::{Point, Vector};
 use crate::util::RayTracerFloat;
@@ -10,9 +11,19 @@ pub struct Ray {
 
 impl Ray {
     pub fn new(origin: Point, direction: Vector) -> Ray {
-        assert!(origin.is_point());
-        assert!(direction.is_vector());
-        Ray { origin, direction }
+        Self::try_new(origin, direction).unwrap_or_else(|e| panic!("invalid ray: {}", e))
+    }
+
+    pub fn try_new(origin: Point, direction: Vector) -> Result<Ray> {
+        if !origin.is_point() {
+            return Err(Error::NotAPoint(origin));
+        }
+
+        if !direction.is_vector() {
+            return Err(Error::NotAVector(direction));
+        }
+
+        Ok(Ray { origin, direction })
     }
 
     pub fn position(&self, t: RayTracerFloat) -> Point {
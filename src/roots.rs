@@ -0,0 +1,264 @@
+use std::{f64::consts::FRAC_PI_3, ops::Deref};
+
+use crate::util::RayTracerFloat;
+
+/// How close to zero a value must be to be treated as zero while solving
+const TOLERANCE: RayTracerFloat = 1e-9;
+
+/// The real roots of a polynomial of degree four or less, in ascending order. Stored inline so
+/// solving allocates nothing.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Roots {
+    values: [RayTracerFloat; 4],
+    len: usize,
+}
+
+impl Roots {
+    fn push(&mut self, root: RayTracerFloat) {
+        self.values[self.len] = root;
+        self.len += 1;
+    }
+
+    fn sorted(mut self) -> Self {
+        self.values[..self.len].sort_unstable_by(|a, b| a.total_cmp(b));
+        self
+    }
+}
+
+impl Deref for Roots {
+    type Target = [RayTracerFloat];
+
+    fn deref(&self) -> &[RayTracerFloat] {
+        &self.values[..self.len]
+    }
+}
+
+fn is_zero(x: RayTracerFloat) -> bool {
+    x.abs() < TOLERANCE
+}
+
+/// Roots of `a x² + b x + c`
+pub fn quadratic(a: RayTracerFloat, b: RayTracerFloat, c: RayTracerFloat) -> Roots {
+    let mut roots = Roots::default();
+
+    if is_zero(a) {
+        if !is_zero(b) {
+            roots.push(-c / b);
+        }
+        return roots;
+    }
+
+    let p = b / (2. * a);
+    let discriminant = p * p - c / a;
+
+    if is_zero(discriminant) {
+        roots.push(-p);
+    } else if discriminant > 0. {
+        // avoids cancellation between -p and the square root
+        let q = -p - discriminant.sqrt().copysign(p);
+        roots.push(q);
+        roots.push(if q == 0. { 0. } else { (c / a) / q });
+    }
+
+    roots.sorted()
+}
+
+/// Roots of `a x³ + b x² + c x + d`
+pub fn cubic(a: RayTracerFloat, b: RayTracerFloat, c: RayTracerFloat, d: RayTracerFloat) -> Roots {
+    if is_zero(a) {
+        return quadratic(b, c, d);
+    }
+
+    // x³ + A x² + B x + C, then x = y - A/3 gives y³ + 3p y + 2q
+    let (a, b, c) = (b / a, c / a, d / a);
+    let p = (b - a * a / 3.) / 3.;
+    let q = (2. / 27. * a * a * a - a * b / 3. + c) / 2.;
+    let discriminant = q * q + p * p * p;
+
+    let mut roots = Roots::default();
+
+    if is_zero(discriminant) {
+        if is_zero(q) {
+            roots.push(0.);
+        } else {
+            let u = (-q).cbrt();
+            roots.push(2. * u);
+            roots.push(-u);
+        }
+    } else if discriminant < 0. {
+        // three real roots
+        let phi = (-q / (-p * p * p).sqrt()).clamp(-1., 1.).acos() / 3.;
+        let t = 2. * (-p).sqrt();
+        roots.push(t * phi.cos());
+        roots.push(-t * (phi + FRAC_PI_3).cos());
+        roots.push(-t * (phi - FRAC_PI_3).cos());
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        roots.push((sqrt_d - q).cbrt() - (sqrt_d + q).cbrt());
+    }
+
+    for r in &mut roots.values[..roots.len] {
+        *r -= a / 3.;
+    }
+
+    roots.sorted()
+}
+
+/// Roots of `a x⁴ + b x³ + c x² + d x + e`, found with Ferrari's method and then polished with
+/// Newton's method against the original polynomial to recover the precision the closed form
+/// loses
+pub fn quartic(
+    a: RayTracerFloat,
+    b: RayTracerFloat,
+    c: RayTracerFloat,
+    d: RayTracerFloat,
+    e: RayTracerFloat,
+) -> Roots {
+    if is_zero(a) {
+        return cubic(b, c, d, e);
+    }
+
+    // x⁴ + A x³ + B x² + C x + D, then x = y - A/4 gives y⁴ + p y² + q y + r
+    let (qa, qb, qc, qd) = (b / a, c / a, d / a, e / a);
+    let sq = qa * qa;
+    let p = -3. / 8. * sq + qb;
+    let q = sq * qa / 8. - qa * qb / 2. + qc;
+    let r = -3. / 256. * sq * sq + sq * qb / 16. - qa * qc / 4. + qd;
+
+    let mut roots = Roots::default();
+
+    if is_zero(r) {
+        // y (y³ + p y + q) = 0
+        roots.push(0.);
+        for y in cubic(1., 0., p, q).iter() {
+            roots.push(*y);
+        }
+    } else {
+        // the largest root of the resolvent cubic makes 2z - p non-negative, splitting the
+        // quartic into (y² + z)² = (v y - u)²
+        let Some(&z) = cubic(1., -p / 2., -r, r * p / 2. - q * q / 8.).last() else {
+            return roots;
+        };
+
+        let v = 2. * z - p;
+        if v < -TOLERANCE {
+            return roots;
+        }
+        let v = v.max(0.).sqrt();
+
+        let u = if is_zero(v) {
+            let u = z * z - r;
+            if u < -TOLERANCE {
+                return roots;
+            }
+            u.max(0.).sqrt()
+        } else {
+            q / (2. * v)
+        };
+
+        for y in quadratic(1., -v, z + u).iter() {
+            roots.push(*y);
+        }
+        for y in quadratic(1., v, z - u).iter() {
+            roots.push(*y);
+        }
+    }
+
+    let value = |x: RayTracerFloat| (((a * x + b) * x + c) * x + d) * x + e;
+    let slope = |x: RayTracerFloat| ((4. * a * x + 3. * b) * x + 2. * c) * x + d;
+
+    for x in &mut roots.values[..roots.len] {
+        *x -= qa / 4.;
+
+        for _ in 0..2 {
+            let s = slope(*x);
+            if s == 0. {
+                break;
+            }
+
+            let polished = *x - value(*x) / s;
+            if !polished.is_finite() || value(polished).abs() > value(*x).abs() {
+                break;
+            }
+            *x = polished;
+        }
+    }
+
+    roots.sorted()
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+
+    use super::{cubic, quadratic, quartic};
+
+    #[test]
+    fn quadratic_roots() {
+        assert_eq!(&*quadratic(1., -3., 2.), &[1., 2.]);
+        assert_eq!(&*quadratic(1., 2., 1.), &[-1.]);
+        assert!(quadratic(1., 0., 1.).is_empty());
+        assert_eq!(&*quadratic(0., 2., -4.), &[2.]);
+    }
+
+    #[test]
+    fn cubic_roots() {
+        // (x - 1)(x - 2)(x + 3)
+        let roots = cubic(1., 0., -7., 6.);
+        assert_eq!(roots.len(), 3);
+        for (r, e) in roots.iter().zip([-3., 1., 2.]) {
+            assert_abs_diff_eq!(*r, e, epsilon = 1e-9);
+        }
+
+        // (x - 2)(x² + 1)
+        let roots = cubic(1., -2., 1., -2.);
+        assert_eq!(roots.len(), 1);
+        assert_abs_diff_eq!(roots[0], 2., epsilon = 1e-9);
+    }
+
+    #[test]
+    fn quartic_roots() {
+        // (x - 1)(x - 2)(x - 3)(x - 4)
+        let roots = quartic(1., -10., 35., -50., 24.);
+        assert_eq!(roots.len(), 4);
+        for (r, e) in roots.iter().zip([1., 2., 3., 4.]) {
+            assert_abs_diff_eq!(*r, e, epsilon = 1e-9);
+        }
+
+        // roots placed asymmetrically, so that the cubic term doesn't vanish once depressed
+        let roots = quartic(1., -11., 41., -61., 30.);
+        assert_eq!(roots.len(), 4);
+        for (r, e) in roots.iter().zip([1., 2., 3., 5.]) {
+            assert_abs_diff_eq!(*r, e, epsilon = 1e-9);
+        }
+
+        // (x - 1)(x - 2)(x² + 1)
+        let roots = quartic(1., -3., 3., -3., 2.);
+        assert_eq!(roots.len(), 2);
+        assert_abs_diff_eq!(roots[0], 1., epsilon = 1e-9);
+        assert_abs_diff_eq!(roots[1], 2., epsilon = 1e-9);
+
+        // (x² + 1)(x² + 4) has no real roots
+        assert!(quartic(1., 0., 5., 0., 4.).is_empty());
+
+        // (x - 0.5)(x + 0.5)(x² + 1), scaled
+        let roots = quartic(4., 0., 3., 0., -1.);
+        assert_eq!(roots.len(), 2);
+        assert_abs_diff_eq!(roots[0], -0.5, epsilon = 1e-9);
+        assert_abs_diff_eq!(roots[1], 0.5, epsilon = 1e-9);
+
+        // close roots far from zero, as for a ray starting far from a torus
+        let (r1, r2, r3, r4) = (998.75, 999.25, 1000.75, 1001.25);
+        let roots = quartic(
+            1.,
+            -(r1 + r2 + r3 + r4),
+            r1 * r2 + r1 * r3 + r1 * r4 + r2 * r3 + r2 * r4 + r3 * r4,
+            -(r1 * r2 * r3 + r1 * r2 * r4 + r1 * r3 * r4 + r2 * r3 * r4),
+            r1 * r2 * r3 * r4,
+        );
+        assert_eq!(roots.len(), 4);
+        for (r, e) in roots.iter().zip([r1, r2, r3, r4]) {
+            assert_abs_diff_eq!(*r, e, epsilon = 1e-6);
+        }
+    }
+}
@@ -0,0 +1,112 @@
+use std::f64::consts::TAU;
+
+use crate::{
+    tuple::{Point, Vector},
+    util::RayTracerFloat,
+};
+
+/// The golden ratio's fractional part; successive multiples of it are spread evenly over [0, 1)
+const GOLDEN: RayTracerFloat = 0.618_033_988_749_895;
+
+/// A well-mixed hash of a point's exact coordinates, for decorrelating sample patterns between
+/// neighboring hits without a random number generator
+pub fn hash_point(p: &Point) -> u64 {
+    [p.x(), p.y(), p.z()]
+        .iter()
+        .fold(0x9e37_79b9_7f4a_7c15, |h, c| splitmix(h ^ c.to_bits()))
+}
+
+fn splitmix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Maps a hash to [0, 1)
+fn unit(h: u64) -> RayTracerFloat {
+    (h >> 11) as RayTracerFloat / (1u64 << 53) as RayTracerFloat
+}
+
+/// `count` normalized directions spread evenly over the cone of half-angle `half_angle` around
+/// `axis` (which need not be normalized), rotated by an offset derived from `seed`. They are
+/// generated as they are read, so sampling allocates nothing.
+pub fn cone_samples(
+    axis: &Vector,
+    half_angle: RayTracerFloat,
+    count: usize,
+    seed: u64,
+) -> impl ExactSizeIterator<Item = Vector> {
+    let w = axis.normalize();
+
+    // any vector not parallel to w will do for building a basis
+    let helper = if w.x().abs() < 0.9 {
+        Vector::vector(1., 0., 0.)
+    } else {
+        Vector::vector(0., 1., 0.)
+    };
+    let u = helper.cross(&w).normalize();
+    let v = w.cross(&u);
+
+    let cos_max = half_angle.min(TAU / 4.).cos();
+    let (radial_offset, angular_offset) = (unit(seed), unit(splitmix(seed)));
+
+    (0..count).map(move |i| {
+        // stratified over equal solid angles, with the spiral starting at a seeded angle
+        let r = ((i as RayTracerFloat + radial_offset) / count as RayTracerFloat).min(1.);
+        let cos_theta = 1. - r * (1. - cos_max);
+        let sin_theta = (1. - cos_theta * cos_theta).sqrt();
+        let phi = TAU * (i as RayTracerFloat * GOLDEN + angular_offset).fract();
+
+        (u * (sin_theta * phi.cos()) + v * (sin_theta * phi.sin()) + w * cos_theta).normalize()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use approx::assert_abs_diff_eq;
+
+    use crate::tuple::{Point, Vector};
+
+    use super::{cone_samples, hash_point};
+
+    #[test]
+    fn samples_stay_inside_cone() {
+        let axis = Vector::vector(1., 2., -0.5);
+        let half_angle: f64 = 0.3;
+
+        let samples = cone_samples(&axis, half_angle, 64, 42);
+
+        assert_eq!(samples.len(), 64);
+
+        for s in samples {
+            assert_abs_diff_eq!(s.magnitude(), 1.);
+            assert!(s.dot(&axis.normalize()) >= half_angle.cos() - 1e-9);
+        }
+    }
+
+    #[test]
+    fn zero_angle_gives_the_axis() {
+        for s in cone_samples(&Vector::vector(0., 0., 3.), 0., 4, 7) {
+            assert_abs_diff_eq!(s, Vector::vector(0., 0., 1.));
+        }
+    }
+
+    #[test]
+    fn samples_average_to_axis() {
+        let axis = Vector::vector(0., 1., 0.);
+        let mean =
+            cone_samples(&axis, 0.5, 256, 3).fold(Vector::vector(0., 0., 0.), |acc, s| acc + s);
+
+        assert_abs_diff_eq!(mean.normalize(), axis, epsilon = 0.01);
+    }
+
+    #[test]
+    fn hash_depends_on_every_coordinate() {
+        let p = Point::point(1., 2., 3.);
+
+        assert_eq!(hash_point(&p), hash_point(&Point::point(1., 2., 3.)));
+        assert_ne!(hash_point(&p), hash_point(&Point::point(1., 2., 3.000001)));
+        assert_ne!(hash_point(&p), hash_point(&Point::point(2., 1., 3.)));
+    }
+}
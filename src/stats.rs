@@ -0,0 +1,191 @@
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+/// The kinds of rays traced while rendering a scene
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum RayKind {
+    Primary,
+    Shadow,
+    Reflection,
+    Refraction,
+}
+
+/// Counters collected while rendering a scene; see `collect`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RenderStats {
+    pub primary_rays: usize,
+    pub shadow_rays: usize,
+    pub reflection_rays: usize,
+    pub refraction_rays: usize,
+    /// `Object::intersections` calls, keyed by `ObjectType::name`
+    pub intersection_tests: BTreeMap<&'static str, usize>,
+    pub inverse_computations: usize,
+    /// deepest level of reflection/refraction recursion; primary rays are depth 0
+    pub max_depth: usize,
+    pub wall_time: Duration,
+    current_depth: usize,
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<RenderStats>> = const { RefCell::new(None) };
+}
+
+/// Runs `f`, collecting render statistics for everything it traces on this thread
+pub fn collect<T>(f: impl FnOnce() -> T) -> (T, RenderStats) {
+    let outer = ACTIVE.with(|a| a.replace(Some(RenderStats::default())));
+
+    let start = Instant::now();
+    let result = f();
+    let wall_time = start.elapsed();
+
+    let mut stats = ACTIVE.with(|a| a.replace(outer)).unwrap_or_default();
+    stats.wall_time = wall_time;
+
+    (result, stats)
+}
+
+fn record(f: impl FnOnce(&mut RenderStats)) {
+    ACTIVE.with(|a| {
+        if let Some(stats) = a.borrow_mut().as_mut() {
+            f(stats);
+        }
+    });
+}
+
+pub(crate) fn record_ray(kind: RayKind) {
+    record(|s| match kind {
+        RayKind::Primary => s.primary_rays += 1,
+        RayKind::Shadow => s.shadow_rays += 1,
+        RayKind::Reflection => s.reflection_rays += 1,
+        RayKind::Refraction => s.refraction_rays += 1,
+    });
+}
+
+pub(crate) fn record_intersection_test(object_type: &'static str) {
+    record(|s| *s.intersection_tests.entry(object_type).or_default() += 1);
+}
+
+pub(crate) fn record_inverse() {
+    record(|s| s.inverse_computations += 1);
+}
+
+/// Marks one level of secondary-ray recursion until the returned guard is dropped
+pub(crate) fn descend() -> DepthGuard {
+    record(|s| {
+        s.current_depth += 1;
+        s.max_depth = s.max_depth.max(s.current_depth);
+    });
+
+    DepthGuard
+}
+
+pub(crate) struct DepthGuard;
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        record(|s| s.current_depth = s.current_depth.saturating_sub(1));
+    }
+}
+
+impl RenderStats {
+    pub fn total_rays(&self) -> usize {
+        self.primary_rays + self.shadow_rays + self.reflection_rays + self.refraction_rays
+    }
+}
+
+impl Display for RenderStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "render statistics:")?;
+        writeln!(f, "  wall time:             {:.3?}", self.wall_time)?;
+        writeln!(f, "  primary rays:          {}", self.primary_rays)?;
+        writeln!(f, "  shadow rays:           {}", self.shadow_rays)?;
+        writeln!(f, "  reflection rays:       {}", self.reflection_rays)?;
+        writeln!(f, "  refraction rays:       {}", self.refraction_rays)?;
+        writeln!(f, "  total rays:            {}", self.total_rays())?;
+        writeln!(f, "  max depth reached:     {}", self.max_depth)?;
+        writeln!(f, "  inverse computations:  {}", self.inverse_computations)?;
+        writeln!(f, "  intersection tests:")?;
+
+        for (object_type, count) in &self.intersection_tests {
+            writeln!(f, "    {:<20} {}", object_type, count)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{f64::consts::FRAC_PI_2, rc::Rc};
+
+    use crate::{
+        camera::Camera,
+        tuple::{Point, Vector},
+        world::World,
+    };
+
+    use super::{collect, descend, record_intersection_test, record_ray, RayKind};
+
+    #[test]
+    fn nothing_recorded_outside_collect() {
+        record_ray(RayKind::Primary);
+
+        let (_, stats) = collect(|| ());
+        assert_eq!(stats.primary_rays, 0);
+    }
+
+    #[test]
+    fn counts_rays_and_intersection_tests() {
+        let (result, stats) = collect(|| {
+            record_ray(RayKind::Primary);
+            record_ray(RayKind::Shadow);
+            record_ray(RayKind::Shadow);
+            record_intersection_test("sphere");
+            record_intersection_test("sphere");
+            record_intersection_test("plane");
+            42
+        });
+
+        assert_eq!(result, 42);
+        assert_eq!(stats.primary_rays, 1);
+        assert_eq!(stats.shadow_rays, 2);
+        assert_eq!(stats.total_rays(), 3);
+        assert_eq!(stats.intersection_tests["sphere"], 2);
+        assert_eq!(stats.intersection_tests["plane"], 1);
+    }
+
+    #[test]
+    fn tracks_max_depth() {
+        let (_, stats) = collect(|| {
+            let _outer = descend();
+            {
+                let _inner = descend();
+            }
+            let _sibling = descend();
+        });
+
+        assert_eq!(stats.max_depth, 2);
+    }
+
+    #[test]
+    fn render_with_stats_counts_primary_rays() {
+        let w = Rc::new(World::default_world());
+        let c = Camera::new(
+            11,
+            11,
+            FRAC_PI_2,
+            Point::point(0., 0., -5.).view_transform(&Point::origin(), &Vector::vector(0., 1., 0.)),
+        );
+
+        let (image, stats) = c.render_with_stats(&w);
+
+        assert_eq!(image.width(), 11);
+        assert_eq!(stats.primary_rays, 121);
+        assert_eq!(stats.intersection_tests["sphere"], 2 * stats.total_rays());
+        assert_eq!(stats.max_depth, 0);
+    }
+}
@@ -1,9 +1,79 @@
 use nalgebra::Matrix4;
 
-use crate::util::RayTracerFloat;
+use crate::{
+    error::{Error, Result},
+    quaternion::Quaternion,
+    stats,
+    tuple::Vector,
+    util::RayTracerFloat,
+};
 
 pub type Transform = Matrix4<RayTracerFloat>;
 
+/// A transform along with its inverse and inverse transpose, computed once up front
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct InvertibleTransform {
+    matrix: Transform,
+    inverse: Transform,
+    inverse_transpose: Transform,
+}
+
+impl InvertibleTransform {
+    pub fn new(matrix: Transform) -> Result<Self> {
+        stats::record_inverse();
+
+        let inverse = matrix.try_inverse().ok_or(Error::NonInvertibleTransform)?;
+
+        Ok(Self {
+            matrix,
+            inverse,
+            inverse_transpose: inverse.transpose(),
+        })
+    }
+
+    pub fn matrix(&self) -> &Transform {
+        &self.matrix
+    }
+
+    pub fn inverse(&self) -> &Transform {
+        &self.inverse
+    }
+
+    pub fn inverse_transpose(&self) -> &Transform {
+        &self.inverse_transpose
+    }
+
+    /// This transform applied after `inner`. Both inverses are already known, so nothing needs
+    /// inverting.
+    pub fn compose(&self, inner: &InvertibleTransform) -> Self {
+        let inverse = inner.inverse * self.inverse;
+
+        Self {
+            matrix: self.matrix * inner.matrix,
+            inverse,
+            inverse_transpose: inverse.transpose(),
+        }
+    }
+}
+
+impl Default for InvertibleTransform {
+    fn default() -> Self {
+        Self {
+            matrix: identity(),
+            inverse: identity(),
+            inverse_transpose: identity(),
+        }
+    }
+}
+
+impl TryFrom<Transform> for InvertibleTransform {
+    type Error = Error;
+
+    fn try_from(matrix: Transform) -> Result<Self> {
+        Self::new(matrix)
+    }
+}
+
 #[derive(Debug, Copy, Clone)]
 pub enum RotationAxis {
     X,
@@ -11,7 +81,6 @@ pub enum RotationAxis {
     Z,
 }
 
-// TODO fluent api?
 nofmt::pls! {
     pub fn identity() -> Transform {
         Matrix4::identity()
@@ -59,6 +128,22 @@ nofmt::pls! {
         }
     }
 
+    /// Rotation by `r` radians around an arbitrary (non-zero) axis through the origin
+    pub fn rotation_around(axis: &Vector, r: RayTracerFloat) -> Transform {
+        let a = axis.normalize();
+        let (x, y, z) = (a.x(), a.y(), a.z());
+        let (s, c) = r.sin_cos();
+        let t = 1. - c;
+
+        // row-major, unlike from_vec above
+        Matrix4::new(
+            t*x*x + c,   t*x*y - s*z, t*x*z + s*y, 0.,
+            t*x*y + s*z, t*y*y + c,   t*y*z - s*x, 0.,
+            t*x*z - s*y, t*y*z + s*x, t*z*z + c,   0.,
+            0.,          0.,          0.,          1.,
+        )
+    }
+
     pub fn shearing(xy: RayTracerFloat, xz: RayTracerFloat, yx: RayTracerFloat, yz: RayTracerFloat, zx: RayTracerFloat, zy: RayTracerFloat) -> Transform {
         Matrix4::from_vec(vec![
             1., yx, zx, 0.,
@@ -68,3 +153,133 @@ nofmt::pls! {
         ])
     }
 }
+
+/// Chainable construction of transforms that reads in the order the operations are applied,
+/// e.g. `identity().rotate_x(r).scale_by(2., 2., 2.).translate(0., 1., 0.)` rotates first and
+/// translates last (the same as `translation(..) * scaling(..) * rotation(..)`).
+pub trait TransformBuilder {
+    /// Applies `next` after everything already in this transform
+    fn then(&self, next: Transform) -> Transform;
+
+    fn translate(&self, x: RayTracerFloat, y: RayTracerFloat, z: RayTracerFloat) -> Transform {
+        self.then(translation(x, y, z))
+    }
+
+    fn scale_by(&self, x: RayTracerFloat, y: RayTracerFloat, z: RayTracerFloat) -> Transform {
+        self.then(scaling(x, y, z))
+    }
+
+    fn rotate(&self, axis: RotationAxis, r: RayTracerFloat) -> Transform {
+        self.then(rotation(axis, r))
+    }
+
+    fn rotate_x(&self, r: RayTracerFloat) -> Transform {
+        self.rotate(RotationAxis::X, r)
+    }
+
+    fn rotate_y(&self, r: RayTracerFloat) -> Transform {
+        self.rotate(RotationAxis::Y, r)
+    }
+
+    fn rotate_z(&self, r: RayTracerFloat) -> Transform {
+        self.rotate(RotationAxis::Z, r)
+    }
+
+    fn rotate_around(&self, axis: &Vector, r: RayTracerFloat) -> Transform {
+        self.then(rotation_around(axis, r))
+    }
+
+    fn rotate_by(&self, q: &Quaternion) -> Transform {
+        self.then(q.to_transform())
+    }
+
+    fn shear(
+        &self,
+        xy: RayTracerFloat,
+        xz: RayTracerFloat,
+        yx: RayTracerFloat,
+        yz: RayTracerFloat,
+        zx: RayTracerFloat,
+        zy: RayTracerFloat,
+    ) -> Transform {
+        self.then(shearing(xy, xz, yx, yz, zx, zy))
+    }
+}
+
+impl TransformBuilder for Transform {
+    fn then(&self, next: Transform) -> Transform {
+        next * self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use approx::assert_abs_diff_eq;
+
+    use crate::tuple::{Point, Vector};
+
+    use super::{
+        identity, rotation, rotation_around, scaling, translation, InvertibleTransform,
+        RotationAxis, TransformBuilder,
+    };
+
+    #[test]
+    fn fluent_transforms_apply_in_reading_order() {
+        let t = identity()
+            .rotate_x(FRAC_PI_2)
+            .scale_by(5., 5., 5.)
+            .translate(10., 5., 7.);
+
+        assert_abs_diff_eq!(
+            t,
+            translation(10., 5., 7.) * scaling(5., 5., 5.) * rotation(RotationAxis::X, FRAC_PI_2)
+        );
+
+        assert_abs_diff_eq!(
+            Point::point(1., 0., 1.).transform(&t),
+            Point::point(15., 0., 7.),
+        );
+    }
+
+    #[test]
+    fn rotation_around_principal_axes_matches_rotation() {
+        for (axis, v) in [
+            (RotationAxis::X, Vector::vector(1., 0., 0.)),
+            (RotationAxis::Y, Vector::vector(0., 2., 0.)),
+            (RotationAxis::Z, Vector::vector(0., 0., 0.5)),
+        ] {
+            assert_abs_diff_eq!(rotation_around(&v, FRAC_PI_4), rotation(axis, FRAC_PI_4));
+        }
+    }
+
+    #[test]
+    fn rotate_around_arbitrary_axis() {
+        // a third of a turn around (1, 1, 1) cycles the axes
+        let t = identity().rotate_around(&Vector::vector(1., 1., 1.), std::f64::consts::TAU / 3.);
+
+        assert_abs_diff_eq!(
+            Point::point(1., 0., 0.).transform(&t),
+            Point::point(0., 1., 0.),
+        );
+    }
+
+    #[test]
+    fn composing_matches_inverting_the_product() {
+        let outer = InvertibleTransform::new(translation(1., 2., 3.)).unwrap();
+        let inner =
+            InvertibleTransform::new(scaling(2., 3., 4.) * rotation(RotationAxis::Y, FRAC_PI_4))
+                .unwrap();
+        let expected = InvertibleTransform::new(outer.matrix() * inner.matrix()).unwrap();
+        let composed = outer.compose(&inner);
+
+        assert_abs_diff_eq!(*composed.matrix(), *expected.matrix(), epsilon = 1e-12);
+        assert_abs_diff_eq!(*composed.inverse(), *expected.inverse(), epsilon = 1e-12);
+        assert_abs_diff_eq!(
+            *composed.inverse_transpose(),
+            *expected.inverse_transpose(),
+            epsilon = 1e-12
+        );
+    }
+}
@@ -1,21 +1,56 @@
-use std::rc::Rc;
+use std::{f64::consts::FRAC_PI_2, rc::Rc};
 
 use crate::{
-    color::{Color, BLACK},
-    intersection::{Intersection, Intersections},
+    background::Background,
+    color::{Color, BLACK, WHITE},
+    error::{Error, Result},
+    intersection::{IntersectionBuffer, Intersections},
     light::PointLight,
-    material::{Material, MaterialBuilder},
+    material::{Material, MaterialBuilder, CHANNEL_WAVELENGTHS},
     objects::Object,
     precompute::Precompute,
     ray::Ray,
-    transforms::{identity, scaling},
-    tuple::Point,
+    sampling::{cone_samples, hash_point},
+    stats::{self, RayKind},
+    transforms::{identity, scaling, Transform},
+    tuple::{Point, Vector},
+    util::RayTracerFloat,
 };
 
+/// How a ray is being traced, carried down through every ray spawned from it
+#[derive(Debug, Clone, Copy)]
+struct Trace {
+    /// reflections and refractions still to follow
+    remaining: usize,
+    /// whether the ray is a sample of a rough reflection or refraction
+    rough: bool,
+    /// the wavelength being traced once dispersion has split a ray into its channels
+    wavelength: Option<RayTracerFloat>,
+}
+
+impl Trace {
+    fn new(remaining: usize) -> Self {
+        Self {
+            remaining,
+            rough: false,
+            wavelength: None,
+        }
+    }
+
+    /// The context of a ray spawned by reflecting or refracting this one
+    fn spawned(self) -> Self {
+        Self {
+            remaining: self.remaining - 1,
+            ..self
+        }
+    }
+}
+
 #[derive(Debug, Default)]
 pub struct World {
     pub objects: Vec<Rc<Object>>,
     pub light_source: PointLight,
+    pub background: Background,
 }
 
 impl World {
@@ -23,9 +58,15 @@ impl World {
         Self {
             objects,
             light_source,
+            background: Background::default(),
         }
     }
 
+    pub fn with_background(mut self, background: Background) -> Self {
+        self.background = background;
+        self
+    }
+
     pub fn default_world() -> Self {
         World::new(
             vec![
@@ -51,99 +92,430 @@ impl World {
     }
 
     pub fn intersects_with(&self, r: &Ray) -> Rc<Intersections> {
-        let mut all_intersections: Vec<Rc<Intersection>> = vec![];
+        let mut buf = IntersectionBuffer::new();
+        self.intersect_into(r, &mut buf);
+
+        Intersections::from_hits(buf.hits()).into()
+    }
+
+    /// Replaces the contents of `buf` with every crossing of `r` with the world, sorted by t
+    pub fn intersect_into<'a>(&'a self, r: &Ray, buf: &mut IntersectionBuffer<'a>) {
+        buf.clear();
 
         for o in &self.objects {
-            o.clone()
-                .intersections(r)
-                .ints()
-                .iter()
-                .for_each(|i| all_intersections.push(i.clone()));
+            o.intersect_into(r, buf);
         }
 
-        all_intersections.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        buf.sort();
+    }
 
-        Intersections::new(all_intersections).into()
+    /// Whether `ray` hits some part of an object for which `accept` holds, strictly between
+    /// `EPSILON` and `max_t`. Stops at the first such hit and allocates nothing.
+    pub fn occludes(
+        &self,
+        ray: &Ray,
+        max_t: RayTracerFloat,
+        accept: impl Fn(&Material) -> bool,
+    ) -> bool {
+        self.objects.iter().any(|o| o.occludes(ray, max_t, &accept))
     }
 
+    /// Whether nothing of the light reaches `p`
     pub fn is_shadowed(&self, p: &Point) -> bool {
+        self.light_transmission(p) == BLACK
+    }
+
+    /// The fraction of each channel of the light that reaches `p`. Every surface crossed on the
+    /// way lets through its `transparency`, and the stretches inside objects are further
+    /// dimmed by their `absorption`; objects that don't cast shadows are ignored.
+    pub fn light_transmission(&self, p: &Point) -> Color {
+        self.light_transmission_buffered(p, &mut IntersectionBuffer::new())
+    }
+
+    fn light_transmission_buffered<'a>(
+        &'a self,
+        p: &Point,
+        buf: &mut IntersectionBuffer<'a>,
+    ) -> Color {
         let v = self.light_source.position - *p;
         let distance = v.magnitude();
         let direction = v.normalize();
         let r = Ray::new(*p, direction);
-        let intersections = self.intersects_with(&r);
-        let h = intersections.hit();
+        stats::record_ray(RayKind::Shadow);
 
-        if let Some(hit) = h {
-            hit.t < distance
-        } else {
-            false
+        // most shadow rays are settled by a cheap any-hit query; only light passing through
+        // transparent objects needs the full list of crossings
+        if !self.occludes(&r, distance, |m| m.casts_shadow) {
+            return WHITE;
+        }
+
+        if self.occludes(&r, distance, |m| m.casts_shadow && m.transparency == 0.) {
+            return BLACK;
+        }
+
+        self.intersect_into(&r, buf);
+
+        // objects the shadow ray is inside of, with where it entered them. Only crossings that
+        // pair up into an entry and an exit bound a volume; an open surface such as a plane is
+        // crossed once and absorbs nothing.
+        let (hits, inside) = buf.hits_and_containers();
+        inside.clear();
+        let mut transmission = WHITE;
+
+        for i in hits {
+            // crossings past the light still matter while they may close a volume holding it
+            if transmission == BLACK || (i.t >= distance && inside.is_empty()) {
+                break;
+            }
+
+            let material = i.material();
+
+            if !material.casts_shadow {
+                continue;
+            }
+
+            if let Some(at) = inside.iter().position(|(o, _)| o.same_surface(i)) {
+                let (_, entered) = inside.remove(at);
+                // only the part of the object between the point and the light absorbs
+                let length = i.t.min(distance) - entered.max(0.);
+                if length > 0. {
+                    transmission = transmission * material.transmittance(length);
+                }
+            } else if i.t < distance {
+                inside.push((*i, i.t));
+            }
+
+            if (0. ..distance).contains(&i.t) {
+                let tr = material.transparency;
+                transmission = transmission * Color::new(tr, tr, tr);
+            }
         }
+
+        transmission
     }
 
     pub fn shade_hit(&self, comps: &Precompute, remaining: usize) -> Color {
-        let surface = comps.object.material.lighting(
+        self.shade_hit_buffered(comps, Trace::new(remaining), &mut IntersectionBuffer::new())
+    }
+
+    fn shade_hit_buffered<'a>(
+        &'a self,
+        comps: &Precompute,
+        trace: Trace,
+        buf: &mut IntersectionBuffer<'a>,
+    ) -> Color {
+        let transmission = if comps.object.material.receives_shadow {
+            self.light_transmission_buffered(&comps.over_point, buf)
+        } else {
+            WHITE
+        };
+
+        let surface = comps.object.material.lighting_through(
             comps.object.as_ref(),
             self.light_source,
             comps.point,
             comps.eyev,
             comps.normalv,
-            self.is_shadowed(&comps.over_point),
+            transmission,
         );
 
-        let reflected = self.reflected_color_at(comps, remaining);
-        let refracted = self.refracted_color_at(comps, remaining);
+        let reflected = self.reflected_color_buffered(comps, trace, buf);
+        let refracted = self.refracted_color_buffered(comps, trace, buf);
 
-        let mat = comps.object.material;
+        let mat = &comps.object.material;
 
         if mat.reflective > 0. && mat.transparency > 0. {
-            let reflectance = comps.schlick();
-            surface + (reflected * reflectance) + (refracted * (1.0 - reflectance))
+            let reflectance = comps.reflectance();
+            surface + (reflected * reflectance) + (refracted * (WHITE - reflectance))
         } else {
             surface + reflected + refracted
         }
     }
 
     pub fn color_at(&self, ray: &Ray, remaining: usize) -> Color {
-        let xs = self.intersects_with(ray);
+        self.color_at_buffered(ray, remaining, &mut IntersectionBuffer::new())
+    }
+
+    /// `color_at`, collecting intersections in `buf` for this ray and every ray spawned from
+    /// it. Reusing one buffer across many rays avoids allocating for each of them.
+    pub fn color_at_buffered<'a>(
+        &'a self,
+        ray: &Ray,
+        remaining: usize,
+        buf: &mut IntersectionBuffer<'a>,
+    ) -> Color {
+        self.traced_color_at(ray, Trace::new(remaining), buf)
+    }
 
-        if let Some(hit) = xs.clone().hit() {
-            self.shade_hit(&hit.precompute_with(ray, xs.clone()), remaining)
+    fn traced_color_at<'a>(
+        &'a self,
+        ray: &Ray,
+        trace: Trace,
+        buf: &mut IntersectionBuffer<'a>,
+    ) -> Color {
+        self.intersect_into(ray, buf);
+
+        if let Some(hit) = buf.hit() {
+            let comps = buf.precompute(hit, ray);
+            self.shade_hit_buffered(&comps, trace, buf) * comps.transmittance()
         } else {
-            BLACK
+            self.background.color_for(&ray.direction)
         }
     }
 
     pub fn reflected_color_at(&self, comps: &Precompute, remaining: usize) -> Color {
+        self.reflected_color_buffered(comps, Trace::new(remaining), &mut IntersectionBuffer::new())
+    }
+
+    fn reflected_color_buffered<'a>(
+        &'a self,
+        comps: &Precompute,
+        trace: Trace,
+        buf: &mut IntersectionBuffer<'a>,
+    ) -> Color {
         let reflective = comps.object.material.reflective;
 
-        if reflective == 0. || remaining == 0 {
+        if reflective == 0. || trace.remaining == 0 {
             return BLACK;
         }
 
-        if remaining == 0 {
+        if trace.remaining == 0 {
             BLACK
         } else {
-            let reflect_ray = Ray::new(comps.over_point, comps.reflectv);
-            let color = self.color_at(&reflect_ray, remaining - 1);
+            let color = self.spread_color_at(
+                comps,
+                Ray::new(comps.over_point, comps.reflectv),
+                comps.normalv,
+                RayKind::Reflection,
+                trace,
+                buf,
+            );
             color * reflective
         }
     }
 
+    /// Dispersive hits split the refracted ray into one ray per channel, each bent by its own
+    /// wavelength's indices; those rays stay monochromatic through any later dispersive hits.
     pub fn refracted_color_at(&self, comps: &Precompute, remaining: usize) -> Color {
-        let n12 = comps.n1 / comps.n2;
+        self.refracted_color_buffered(comps, Trace::new(remaining), &mut IntersectionBuffer::new())
+    }
+
+    fn refracted_color_buffered<'a>(
+        &'a self,
+        comps: &Precompute,
+        trace: Trace,
+        buf: &mut IntersectionBuffer<'a>,
+    ) -> Color {
+        if !comps.is_dispersive() {
+            return self.refracted_color_with(comps, comps.n1, comps.n2, trace, buf);
+        }
+
+        if let Some(w) = trace.wavelength {
+            let (n1, n2) = comps.indices_at(w);
+            return self.refracted_color_with(comps, n1, n2, trace, buf);
+        }
+
+        let [r, g, b] = CHANNEL_WAVELENGTHS.map(|w| {
+            let (n1, n2) = comps.indices_at(w);
+            let channel = Trace {
+                wavelength: Some(w),
+                ..trace
+            };
+            self.refracted_color_with(comps, n1, n2, channel, buf)
+        });
+
+        Color::new(r.red(), g.green(), b.blue())
+    }
+
+    fn refracted_color_with<'a>(
+        &'a self,
+        comps: &Precompute,
+        n1: RayTracerFloat,
+        n2: RayTracerFloat,
+        trace: Trace,
+        buf: &mut IntersectionBuffer<'a>,
+    ) -> Color {
+        let n12 = n1 / n2;
         let cos_i = comps.eyev.dot(&comps.normalv);
         let sin2_t = n12.powi(2) * (1. - cos_i.powi(2));
 
-        if remaining == 0 || sin2_t > 1. || comps.object.material.transparency == 0. {
+        if trace.remaining == 0 || sin2_t > 1. || comps.object.material.transparency == 0. {
             return BLACK;
         }
 
         let cos_t = (1.0 - sin2_t).sqrt();
         let direction = comps.normalv * (n12 * cos_i - cos_t) - comps.eyev * n12;
-        let refract_ray = Ray::new(comps.under_point, direction);
+        let color = self.spread_color_at(
+            comps,
+            Ray::new(comps.under_point, direction),
+            -comps.normalv,
+            RayKind::Refraction,
+            trace,
+            buf,
+        );
+
+        color * comps.object.material.transparency
+    }
+
+    /// Traces `ray`, or for rough materials averages several rays spread around it, folding
+    /// any that would cross the surface back to the `side` the ray belongs on. Rough rays
+    /// spawned from other rough rays trace just one sample each, so the cost doesn't multiply
+    /// with every bounce.
+    fn spread_color_at<'a>(
+        &'a self,
+        comps: &Precompute,
+        ray: Ray,
+        side: Vector,
+        kind: RayKind,
+        trace: Trace,
+        buf: &mut IntersectionBuffer<'a>,
+    ) -> Color {
+        let material = &comps.object.material;
+
+        if material.roughness <= 0. {
+            stats::record_ray(kind);
+            let _depth = stats::descend();
+            return self.traced_color_at(&ray, trace.spawned(), buf);
+        }
+
+        let count = if trace.rough {
+            1
+        } else {
+            material.roughness_samples.max(1)
+        };
+        let (origin, direction) = (ray.origin, ray.direction);
+
+        let start = buf.directions().len();
+        buf.directions().extend(
+            cone_samples(
+                &direction,
+                material.roughness * FRAC_PI_2,
+                count,
+                hash_point(&origin),
+            )
+            .map(|d| match d.dot(&side) {
+                cos if cos < 0. => d - side * (2. * cos),
+                _ => d,
+            }),
+        );
+
+        let sample = Trace {
+            rough: true,
+            ..trace.spawned()
+        };
+
+        let mut total = BLACK;
+        for i in start..start + count {
+            let d = buf.directions()[i];
+            stats::record_ray(kind);
+            let _depth = stats::descend();
+            total = total + self.traced_color_at(&Ray::new(origin, d), sample, buf);
+        }
+
+        buf.directions().truncate(start);
+
+        total * (1. / count as RayTracerFloat)
+    }
+
+    /// The object at `path`: the names of a top-level object and of each group beneath it on
+    /// the way down, separated by `/`
+    pub fn find(&self, path: &str) -> Option<&Rc<Object>> {
+        let mut names = path.split('/');
+        let first = names.next()?;
+        let mut object = self.objects.iter().find(|o| o.name() == Some(first))?;
+
+        for name in names {
+            object = &object.children()[object.child_index(name)?];
+        }
 
-        self.color_at(&refract_ray, remaining - 1) * comps.object.material.transparency
+        Some(object)
+    }
+
+    /// The object at `path`, for editing; shared objects on the way are copied first
+    fn find_mut(&mut self, path: &str) -> Result<&mut Object> {
+        let missing = || Error::NoObjectAt(path.to_string());
+        let mut names = path.split('/');
+        let first = names.next().ok_or_else(missing)?;
+
+        let mut object = self
+            .objects
+            .iter_mut()
+            .find(|o| o.name() == Some(first))
+            .map(Rc::make_mut)
+            .ok_or_else(missing)?;
+
+        for name in names {
+            object = object.child_mut(name).ok_or_else(missing)?;
+        }
+
+        Ok(object)
+    }
+
+    /// Replaces the transform of the object at `path`, relative to its group
+    pub fn set_transform(&mut self, path: &str, transform: Transform) -> Result<()> {
+        self.find_mut(path)?.set_transform(transform)
+    }
+
+    /// Gives the object at `path` its own material, which its inheriting descendants share
+    pub fn set_material(&mut self, path: &str, material: Material) -> Result<()> {
+        self.find_mut(path)?.set_material(material);
+        Ok(())
+    }
+
+    /// Adds `object` to the group at `parent`, or at the top level if `parent` is empty
+    pub fn add(&mut self, parent: &str, object: Rc<Object>) -> Result<()> {
+        if parent.is_empty() {
+            self.objects.push(object);
+            Ok(())
+        } else {
+            self.find_mut(parent)?.add_child(object)
+        }
+    }
+
+    /// Takes the object at `path` out of the world
+    pub fn remove(&mut self, path: &str) -> Result<Rc<Object>> {
+        let missing = || Error::NoObjectAt(path.to_string());
+
+        match path.rsplit_once('/') {
+            Some((parent, name)) => {
+                let group = self.find_mut(parent)?;
+                let index = group.child_index(name).ok_or_else(missing)?;
+                group.remove_child(index)
+            }
+            None => {
+                let index = self
+                    .objects
+                    .iter()
+                    .position(|o| o.name() == Some(path))
+                    .ok_or_else(missing)?;
+                Ok(self.objects.remove(index))
+            }
+        }
+    }
+
+    /// Moves the object at `path` into the group at `new_parent`, or to the top level if
+    /// `new_parent` is empty. Its transform is kept, now relative to its new group.
+    pub fn reparent(&mut self, path: &str, new_parent: &str) -> Result<()> {
+        if new_parent == path || new_parent.starts_with(&format!("{}/", path)) {
+            return Err(Error::InvalidParent(path.to_string()));
+        }
+
+        self.find(path)
+            .ok_or_else(|| Error::NoObjectAt(path.to_string()))?;
+
+        // check the destination before taking anything out
+        if !new_parent.is_empty() {
+            let parent = self
+                .find(new_parent)
+                .ok_or_else(|| Error::NoObjectAt(new_parent.to_string()))?;
+
+            if !parent.is_group() {
+                return Err(Error::NotAGroup);
+            }
+        }
+
+        let object = self.remove(path)?;
+        self.add(new_parent, object)
     }
 
     #[cfg(test)]
@@ -154,19 +526,25 @@ impl World {
 
 #[cfg(test)]
 mod test {
-    use std::{f64::consts::SQRT_2, rc::Rc};
+    use std::{
+        f64::consts::{PI, SQRT_2},
+        rc::Rc,
+    };
 
     use approx::assert_abs_diff_eq;
 
     use crate::{
+        background::Background,
         color::{Color, BLACK, WHITE},
+        error::Error,
         intersection::{Intersection, Intersections},
         light::PointLight,
-        material::{Material, MaterialBuilder},
+        material::{Material, MaterialBuilder, DISPERSION_DIAMOND, REFRAC_DIAMOND},
         objects::Object,
         patterns::test::default_test_pattern,
         ray::Ray,
-        transforms::{identity, scaling, translation},
+        stats,
+        transforms::{identity, rotation, scaling, translation, RotationAxis},
         tuple::{Point, Vector},
     };
 
@@ -277,9 +655,10 @@ mod test {
 
         let xs = Intersections::new(vec![Intersection::new(SQRT_2, floor).into()]);
         let comps = xs.ints()[0].clone().precompute_with(&r, xs.into());
+        // the ball is lit by the half of the light that passes through the floor
         assert_abs_diff_eq!(
             w.shade_hit(&comps, 5),
-            Color::new(0.93642, 0.68642, 0.68642)
+            Color::new(1.12547, 0.68642, 0.68642)
         );
     }
 
@@ -314,9 +693,10 @@ mod test {
 
         let xs = Intersections::new(vec![Intersection::new(SQRT_2, floor).into()]);
         let comps = xs.ints()[0].clone().precompute_with(&r, xs.into());
+        // the ball is lit by the half of the light that passes through the floor
         assert_abs_diff_eq!(
             w.shade_hit(&comps, 5),
-            Color::new(0.93391, 0.69643, 0.69243)
+            Color::new(1.11500, 0.69643, 0.69243)
         );
     }
 
@@ -456,7 +836,7 @@ mod test {
                 .diffuse(0.7)
                 .specular(0.2)
                 .ambient(1.0)
-                .pattern(default_test_pattern().into())
+                .pattern(default_test_pattern())
                 .build(),
         ));
 
@@ -486,4 +866,452 @@ mod test {
             Color::new(0., 0.99888, 0.04725),
         );
     }
+
+    #[test]
+    fn missed_rays_see_background() {
+        let sky = Color::new(0.2, 0.4, 0.9);
+        let w = World::default_world().with_background(Background::Solid(sky));
+
+        let r = Ray::new(Point::point(0., 0., -5.), Vector::vector(0., 1., 0.));
+        assert_eq!(w.color_at(&r, 5), sky);
+    }
+
+    #[test]
+    fn reflections_see_background() {
+        let mirror = Rc::new(Object::plane(
+            identity(),
+            MaterialBuilder::default()
+                .color(BLACK)
+                .ambient(0.)
+                .diffuse(0.)
+                .specular(0.)
+                .reflective(1.)
+                .build(),
+        ));
+
+        let w =
+            World::default_world_with_objects(vec![mirror]).with_background(Background::Gradient {
+                bottom: BLACK,
+                top: WHITE,
+            });
+
+        let r = Ray::new(Point::point(0., 1., -1.), Vector::vector(0., -1., 1.));
+
+        // the reflected ray heads up and away at 45°
+        assert_abs_diff_eq!(w.color_at(&r, 5), WHITE * ((SQRT_2 / 2. + 1.) / 2.));
+    }
+
+    fn clear_plane(material: MaterialBuilder) -> World {
+        let plane = Rc::new(Object::plane(
+            identity(),
+            material
+                .color(BLACK)
+                .ambient(0.)
+                .diffuse(0.)
+                .specular(0.)
+                .build(),
+        ));
+
+        World::default_world_with_objects(vec![plane]).with_background(Background::Gradient {
+            bottom: BLACK,
+            top: WHITE,
+        })
+    }
+
+    #[test]
+    fn rough_reflection_blurs_but_stays_above_surface() {
+        let r = Ray::new(Point::point(0., 1., 0.), Vector::vector(0., -1., 0.));
+        let sharp = clear_plane(MaterialBuilder::default().reflective(1.));
+        let rough = clear_plane(
+            MaterialBuilder::default()
+                .reflective(1.)
+                .roughness(1.)
+                .roughness_samples(32),
+        );
+
+        assert_abs_diff_eq!(sharp.color_at(&r, 5), WHITE);
+
+        let blurred = rough.color_at(&r, 5);
+        assert!(blurred.red() < 0.95);
+        assert!(blurred.red() >= 0.5);
+    }
+
+    #[test]
+    fn frosted_refraction_blurs_but_stays_below_surface() {
+        let r = Ray::new(Point::point(0., 1., 0.), Vector::vector(0., -1., 0.));
+        let frosted = clear_plane(
+            MaterialBuilder::default()
+                .transparency(1.)
+                .refractive(1.)
+                .roughness(1.)
+                .roughness_samples(32),
+        );
+
+        let blurred = frosted.color_at(&r, 5);
+        assert!(blurred.red() > 0.05);
+        assert!(blurred.red() <= 0.5);
+    }
+
+    #[test]
+    fn rough_rays_spawn_one_sample_each() {
+        let mirror = |transform| {
+            Rc::new(Object::plane(
+                transform,
+                MaterialBuilder::default()
+                    .reflective(1.)
+                    .roughness(0.5)
+                    .roughness_samples(8)
+                    .build(),
+            ))
+        };
+        let w = World::new(
+            vec![
+                mirror(identity()),
+                // facing down, towards the other mirror
+                mirror(translation(0., 2., 0.) * rotation(RotationAxis::X, PI)),
+            ],
+            PointLight::new(Point::point(0., 1., -10.), WHITE),
+        );
+        let r = Ray::new(Point::point(0., 1., 0.), Vector::vector(0., -1., 0.));
+
+        // eight samples at the first bounce, then one more per sample at each of the next three
+        let (_, stats) = stats::collect(|| w.color_at(&r, 4));
+        assert_eq!(stats.reflection_rays, 8 * 4);
+    }
+
+    #[test]
+    fn rough_reflections_are_deterministic() {
+        let r = Ray::new(Point::point(0.3, 1., -0.2), Vector::vector(0., -1., 0.5));
+        let w = clear_plane(MaterialBuilder::default().reflective(1.).roughness(0.3));
+
+        assert_eq!(w.color_at(&r, 5), w.color_at(&r, 5));
+    }
+
+    fn tinted_glass(radius: f64) -> World {
+        let glass = Rc::new(Object::sphere(
+            scaling(radius, radius, radius),
+            MaterialBuilder::default()
+                .color(BLACK)
+                .ambient(0.)
+                .diffuse(0.)
+                .specular(0.)
+                .transparency(1.)
+                .absorption(Color::new(0., 0.5, 1.))
+                .build(),
+        ));
+
+        World::default_world_with_objects(vec![glass]).with_background(Background::Solid(WHITE))
+    }
+
+    #[test]
+    fn absorption_grows_with_thickness() {
+        let r = Ray::new(Point::point(0., 0., -5.), Vector::vector(0., 0., 1.));
+
+        for radius in [1., 2.] {
+            let thickness: f64 = 2. * radius;
+            assert_abs_diff_eq!(
+                tinted_glass(radius).color_at(&r, 5),
+                Color::new(1., (-0.5 * thickness).exp(), (-thickness).exp()),
+                epsilon = 1e-4
+            );
+        }
+    }
+
+    fn gem(dispersion: f64) -> World {
+        let gem = Rc::new(Object::sphere(
+            identity(),
+            MaterialBuilder::default()
+                .color(BLACK)
+                .ambient(0.)
+                .diffuse(0.)
+                .specular(0.)
+                .transparency(1.)
+                .refractive(REFRAC_DIAMOND)
+                .dispersion(dispersion)
+                .build(),
+        ));
+
+        World::default_world_with_objects(vec![gem]).with_background(Background::Gradient {
+            bottom: BLACK,
+            top: WHITE,
+        })
+    }
+
+    #[test]
+    fn dispersion_splits_channels() {
+        let r = Ray::new(Point::point(0., 0.4, -5.), Vector::vector(0., 0., 1.));
+
+        let clear = gem(0.).color_at(&r, 5);
+        assert_abs_diff_eq!(clear.red(), clear.green());
+        assert_abs_diff_eq!(clear.green(), clear.blue());
+
+        let fire = gem(DISPERSION_DIAMOND).color_at(&r, 5);
+        assert!((fire.red() - fire.green()).abs() > 1e-4);
+        assert!((fire.green() - fire.blue()).abs() > 1e-4);
+    }
+
+    fn overhead_light(blocker: MaterialBuilder) -> World {
+        let sphere = Rc::new(Object::sphere(identity(), blocker.build()));
+        let mut w = World::default_world_with_objects(vec![sphere]);
+        w.light_source = PointLight::new(Point::point(0., 10., 0.), WHITE);
+        w
+    }
+
+    #[test]
+    fn transparent_objects_attenuate_shadows() {
+        let below = Point::point(0., -5., 0.);
+
+        let opaque = overhead_light(MaterialBuilder::default());
+        assert_eq!(opaque.light_transmission(&below), BLACK);
+        assert!(opaque.is_shadowed(&below));
+
+        // crossing both sides of the sphere, and 2 units of its interior
+        let glass = overhead_light(
+            MaterialBuilder::default()
+                .transparency(0.9)
+                .absorption(Color::new(0., 0., 0.5)),
+        );
+        assert_abs_diff_eq!(
+            glass.light_transmission(&below),
+            Color::new(0.81, 0.81, 0.81 * (-1_f64).exp())
+        );
+        assert!(!glass.is_shadowed(&below));
+
+        // starting inside the sphere only the exit and 1 unit of interior count
+        assert_abs_diff_eq!(
+            glass.light_transmission(&Point::point(0., 0., 0.)),
+            Color::new(0.9, 0.9, 0.9 * (-0.5_f64).exp())
+        );
+    }
+
+    #[test]
+    fn absorbing_objects_behind_the_point_leave_shadows_alone() {
+        let below = Point::point(0., -5., 0.);
+        let mut w = overhead_light(MaterialBuilder::default().transparency(0.5));
+        w.objects.push(Rc::new(Object::sphere(
+            translation(0., -10., 0.),
+            MaterialBuilder::default()
+                .transparency(1.)
+                .absorption(Color::new(1., 1., 1.))
+                .build(),
+        )));
+
+        assert_abs_diff_eq!(w.light_transmission(&below), Color::new(0.25, 0.25, 0.25));
+    }
+
+    #[test]
+    fn open_surfaces_absorb_nothing() {
+        let below = Point::point(0., -5., 0.);
+        let mut w = overhead_light(MaterialBuilder::default().transparency(0.5));
+        w.objects.push(Rc::new(Object::plane(
+            translation(0., 5., 0.),
+            MaterialBuilder::default()
+                .transparency(0.5)
+                .absorption(Color::new(1., 1., 1.))
+                .build(),
+        )));
+
+        assert_abs_diff_eq!(
+            w.light_transmission(&below),
+            Color::new(0.125, 0.125, 0.125)
+        );
+    }
+
+    #[test]
+    fn objects_holding_the_light_absorb_up_to_it() {
+        let mut w = overhead_light(MaterialBuilder::default().casts_shadow(false));
+        w.objects.push(Rc::new(Object::sphere(
+            translation(0., 10., 0.) * scaling(2., 2., 2.),
+            MaterialBuilder::default()
+                .transparency(1.)
+                .absorption(Color::new(0., 0., 0.5))
+                .build(),
+        )));
+
+        assert_abs_diff_eq!(
+            w.light_transmission(&Point::point(0., -5., 0.)),
+            Color::new(1., 1., (-1_f64).exp())
+        );
+    }
+
+    #[test]
+    fn objects_can_opt_out_of_casting_shadows() {
+        let w = overhead_light(MaterialBuilder::default().casts_shadow(false));
+        assert_eq!(w.light_transmission(&Point::point(0., -5., 0.)), WHITE);
+    }
+
+    #[test]
+    fn objects_can_opt_out_of_receiving_shadows() {
+        let floor = |receives| {
+            Rc::new(Object::plane(
+                translation(0., -5., 0.),
+                MaterialBuilder::default().receives_shadow(receives).build(),
+            ))
+        };
+        let r = Ray::new(Point::point(0., 0., -5.), Vector::vector(0., -1., 1.));
+
+        let mut shadowed = overhead_light(MaterialBuilder::default());
+        shadowed.objects.push(floor(true));
+        let mut lit = overhead_light(MaterialBuilder::default());
+        lit.objects.push(floor(false));
+        let mut open = World::default_world_with_objects(vec![floor(true)]);
+        open.light_source = lit.light_source;
+
+        assert_abs_diff_eq!(shadowed.color_at(&r, 5), Color::new(0.1, 0.1, 0.1));
+        assert_abs_diff_eq!(lit.color_at(&r, 5), open.color_at(&r, 5));
+    }
+
+    fn furnished() -> World {
+        let table = Object::group(
+            translation(0., 1., 0.),
+            vec![
+                Object::cube(scaling(2., 0.1, 1.), Material::default())
+                    .named("top")
+                    .into(),
+                Rc::unwrap_or_clone(Object::group(
+                    identity(),
+                    vec![Object::sphere(identity(), Material::default())
+                        .named("vase")
+                        .into()],
+                ))
+                .named("shelf")
+                .into(),
+            ],
+        );
+
+        World::default_world_with_objects(vec![
+            Object::plane(identity(), Material::default())
+                .named("floor")
+                .into(),
+            Rc::unwrap_or_clone(table).named("table").into(),
+        ])
+    }
+
+    #[test]
+    fn objects_are_found_by_path() {
+        let w = furnished();
+
+        assert!(w.find("floor").is_some());
+        assert!(w.find("table").unwrap().is_group());
+        assert_eq!(w.find("table/shelf/vase").unwrap().name(), Some("vase"));
+        assert!(w.find("table/vase").is_none());
+        assert!(w.find("chair").is_none());
+    }
+
+    #[test]
+    fn editing_by_path() {
+        let mut w = furnished();
+        let untouched = furnished();
+
+        w.set_transform("table/shelf/vase", translation(0., 2., 0.))
+            .unwrap();
+        assert_abs_diff_eq!(
+            w.find("table/shelf/vase")
+                .unwrap()
+                .world_point_to_local(Point::point(0., 3., 0.)),
+            Point::origin()
+        );
+
+        // moving the table carries everything on it along
+        w.set_transform("table", translation(5., 1., 0.)).unwrap();
+        assert_abs_diff_eq!(
+            w.find("table/shelf/vase")
+                .unwrap()
+                .world_point_to_local(Point::point(5., 3., 0.)),
+            Point::origin()
+        );
+
+        let red = MaterialBuilder::default()
+            .color(Color::new(1., 0., 0.))
+            .build();
+        w.set_material("table/top", red.clone()).unwrap();
+        assert_eq!(w.find("table/top").unwrap().material, red);
+
+        assert_eq!(
+            w.set_material("table/legs", red),
+            Err(Error::NoObjectAt("table/legs".to_string()))
+        );
+
+        // other worlds built from the same pieces are unaffected
+        assert_eq!(
+            untouched.find("table/top").unwrap().material,
+            Material::default()
+        );
+    }
+
+    #[test]
+    fn removing_and_reparenting() {
+        let mut w = furnished();
+
+        w.reparent("table/shelf/vase", "table").unwrap();
+        assert!(w.find("table/shelf/vase").is_none());
+        assert!(w.find("table/vase").is_some());
+
+        w.reparent("table/vase", "").unwrap();
+        assert!(w.find("vase").is_some());
+
+        assert_eq!(
+            w.reparent("table", "table/shelf"),
+            Err(Error::InvalidParent("table".to_string()))
+        );
+        assert_eq!(w.reparent("vase", "floor"), Err(Error::NotAGroup));
+        assert!(w.find("vase").is_some());
+
+        let removed = w.remove("table/top").unwrap();
+        assert_eq!(removed.name(), Some("top"));
+        assert!(w.find("table/top").is_none());
+
+        w.remove("table").unwrap();
+        assert_eq!(w.objects.len(), 2);
+        assert_eq!(
+            w.remove("table").unwrap_err(),
+            Error::NoObjectAt("table".to_string())
+        );
+    }
+
+    #[test]
+    fn instances_render_like_copies() {
+        let red = MaterialBuilder::default()
+            .color(Color::new(1., 0., 0.))
+            .build();
+        let glass = MaterialBuilder::default()
+            .transparency(1.)
+            .refractive(1.5)
+            .build();
+        let sphere = Rc::new(Object::sphere(scaling(0.5, 0.5, 0.5), Material::default()));
+
+        let instanced = World::default_world_with_objects(vec![
+            Rc::new(Object::instance(
+                translation(0., 0., 1.),
+                sphere.clone(),
+                Some(red.clone()),
+            )),
+            Rc::new(Object::instance(
+                identity(),
+                sphere.clone(),
+                Some(glass.clone()),
+            )),
+            Rc::new(Object::instance(
+                translation(0., 0., -1.),
+                sphere,
+                Some(glass.clone()),
+            )),
+        ]);
+        let copied = World::default_world_with_objects(vec![
+            Rc::new(Object::sphere(
+                translation(0., 0., 1.) * scaling(0.5, 0.5, 0.5),
+                red,
+            )),
+            Rc::new(Object::sphere(scaling(0.5, 0.5, 0.5), glass.clone())),
+            Rc::new(Object::sphere(
+                translation(0., 0., -1.) * scaling(0.5, 0.5, 0.5),
+                glass,
+            )),
+        ]);
+
+        for x in [0., 0.1, 0.3] {
+            let r = Ray::new(Point::point(x, 0.2, -5.), Vector::vector(0., 0., 1.));
+            assert_abs_diff_eq!(instanced.color_at(&r, 5), copied.color_at(&r, 5));
+        }
+    }
 }
@@ -1,4 +1,3 @@
-use clap::arg;
 use cucumber::{
     cli::{Args, Opts},
     World,
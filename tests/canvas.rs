@@ -1,9 +1,33 @@
-use cucumber::{gherkin::Step, then, when, World};
+use cucumber::{gherkin::Step, given, then, when, World};
 use futures_lite::future;
-use ray_tracer_challenge_rs::color::Color;
+use ray_tracer_challenge_rs::{canvas::Canvas, color::Color};
 use testutils::world::RayTracerWorld;
 use testutils::RayTracerFloat;
 
+#[given(expr = r"{word} ← a file containing")]
+fn given_file_containing(world: &mut RayTracerWorld, step: &Step, file_name: String) {
+    let contents = step.docstring.as_ref().expect("no docstring");
+    world.files.insert(file_name, contents.clone());
+}
+
+#[when(expr = r"{word} ← canvas_from_ppm\({word}\)")]
+fn when_canvas_from_ppm(world: &mut RayTracerWorld, canvas_name: String, file_name: String) {
+    let file = world.get_file_or_panic(&file_name);
+    let canvas = Canvas::from_ppm(file).expect("could not parse PPM");
+    world.canvases.insert(canvas_name, canvas);
+}
+
+#[then(expr = r"canvas_from_ppm\({word}\) should fail")]
+fn assert_canvas_from_ppm_fails(world: &mut RayTracerWorld, file_name: String) {
+    let file = world.get_file_or_panic(&file_name);
+
+    assert!(
+        Canvas::from_ppm(file).is_err(),
+        "expected {} not to parse as a PPM",
+        file_name
+    );
+}
+
 #[when(expr = r"write_pixel\({word}, {int}, {int}, {word}\)")]
 fn when_write_pixel(
     world: &mut RayTracerWorld,
@@ -12,7 +36,7 @@ fn when_write_pixel(
     y: usize,
     color_name: String,
 ) {
-    let color = world.get_color_or_panic(&color_name).clone();
+    let color = *world.get_color_or_panic(&color_name);
     let canvas = world.get_mut_canvas_or_panic(&canvas_name);
     canvas.write(x, y, color);
 }
@@ -135,9 +159,7 @@ fn assert_ppm_ends_with_newline(world: &mut RayTracerWorld, ppm_name: String) {
     assert!(
         ppm.whole_file()
             .chars()
-            .into_iter()
-            .rev()
-            .next()
+            .next_back()
             .expect("zero size file")
             == '\n',
         "expected ppm {} to end with newline but it did not",
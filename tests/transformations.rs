@@ -11,6 +11,7 @@ use ray_tracer_challenge_rs::transforms::*;
 
 // TODO generalize with above
 #[given(expr = r"{word} ← shearing\({float}, {float}, {float}, {float}, {float}, {float}\)")]
+#[allow(clippy::too_many_arguments)]
 fn given_a_shearing_matrix(
     world: &mut RayTracerWorld,
     matrix_name: String,
@@ -31,7 +32,7 @@ fn given_an_inverse(world: &mut RayTracerWorld, inverted_matrix_name: String, ma
     let m = world.get_transform_or_panic(&matrix_name);
     world.transforms.insert(
         inverted_matrix_name,
-        m.clone().try_inverse().expect("not invertible!"),
+        m.try_inverse().expect("not invertible!"),
     );
 }
 
@@ -45,7 +45,7 @@ fn assert_tuple_type(
         _ => panic!("unrecognized tuple type: {}", tuple_type),
     };
 
-    let negate = negation.len() > 0;
+    let negate = !negation.is_empty();
 
     assert!(
         is_proper_type && !negate || !is_proper_type && negate,
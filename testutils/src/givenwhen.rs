@@ -286,7 +286,7 @@ fn given_sphere_ambient_val(world: &mut RayTracerWorld, s: String, ambient: RayT
     let mut mat = Material::from(&sphere.material);
     mat.ambient = ambient;
 
-    let new_sphere = Object::sphere(sphere.as_ref().transform, mat);
+    let new_sphere = Object::sphere(*sphere.transform(), mat);
     world.objects.insert(s, Rc::new(new_sphere));
 }
 
@@ -294,11 +294,7 @@ fn given_sphere_ambient_val(world: &mut RayTracerWorld, s: String, ambient: RayT
 fn given_material_stripe_pattern(world: &mut RayTracerWorld, m: String) {
     let material = world.get_material_or_panic(&m);
     let mut new_mat = Material::from(material);
-    new_mat.pattern = Pattern::Stripe {
-        even: WHITE,
-        odd: BLACK,
-        transform: identity(),
-    };
+    new_mat.pattern = Pattern::stripe(identity(), WHITE, BLACK);
     world.materials.insert(m, new_mat);
 }
 
@@ -430,7 +426,7 @@ fn when_light_created(
 #[when(expr = r"{word} ← {word}.material")]
 fn when_material_from_sphere(world: &mut RayTracerWorld, mat_name: String, sphere_name: String) {
     let s = world.get_object_or_panic(&sphere_name);
-    world.materials.insert(mat_name, s.material);
+    world.materials.insert(mat_name, s.material.clone());
 }
 
 #[when(expr = r"{word} ← lighting\({word}, {word}, {word}, {word}, {word}\)")]
@@ -670,15 +666,9 @@ fn given_default_plane(world: &mut RayTracerWorld, p: String) {
 fn given_stripe_pattern(world: &mut RayTracerWorld, p: String, c1: String, c2: String) {
     let even = world.get_color_or_panic(&c1);
     let odd = world.get_color_or_panic(&c2);
-    world.patterns.insert(
-        p,
-        Pattern::Stripe {
-            even: *even,
-            odd: *odd,
-            transform: identity(),
-        }
-        .into(),
-    );
+    world
+        .patterns
+        .insert(p, Pattern::stripe(identity(), *even, *odd).into());
 }
 
 #[given(expr = r"set_transform\({word}, {}")]
@@ -702,17 +692,10 @@ fn given_stripe_with_transform(w: &mut RayTracerWorld, p: String, t: String) {
             transform: _,
             even,
             odd,
-        } => (*even, *odd),
+        } => (even.as_ref().clone(), odd.as_ref().clone()),
         _ => panic!("not a stripe!"),
     };
 
-    w.patterns.insert(
-        p,
-        Pattern::Stripe {
-            transform,
-            even,
-            odd,
-        }
-        .into(),
-    );
+    w.patterns
+        .insert(p, Pattern::stripe(transform, even, odd).into());
 }
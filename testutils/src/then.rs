@@ -229,7 +229,7 @@ fn assert_camera_fov(world: &mut RayTracerWorld, fov: RayTracerFloat) {
 #[then(regex = r"^c\.transform = identity_matrix$")]
 fn assert_camera_transform(world: &mut RayTracerWorld) {
     let c = world.get_camera_or_panic(&"c".to_string());
-    assert_eq!(c.transform, identity());
+    assert_eq!(*c.transform(), identity());
 }
 
 #[then(regex = r"^c.pixel_size = (-?\d+(?:\.\d+)?)")]
@@ -312,7 +312,7 @@ fn assert_object_transform_name(world: &mut RayTracerWorld, trans_name: String)
         *world.get_transform_or_panic(&trans_name)
     };
 
-    assert_eq!(o.transform, t)
+    assert_eq!(*o.transform(), t)
 }
 
 #[then(
@@ -328,7 +328,7 @@ fn assert_object_transform_translation(
 
     let t = translation(x, y, z);
 
-    assert_eq!(o.transform, t)
+    assert_eq!(*o.transform(), t)
 }
 
 #[then(regex = r"^s\.material = material\(\)")]
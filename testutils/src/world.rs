@@ -38,6 +38,7 @@ pub struct RayTracerWorld {
     pub precomps: HashMap<String, Precompute>,
     pub cameras: HashMap<String, Camera>,
     pub patterns: HashMap<String, Rc<Pattern>>,
+    pub files: HashMap<String, String>,
 }
 
 // TODO this seems like a job for... a macro!
@@ -60,6 +61,12 @@ impl RayTracerWorld {
             .unwrap_or_else(|| panic!("missing color named {}", color_name))
     }
 
+    pub fn get_file_or_panic(&self, file_name: &String) -> &String {
+        self.files
+            .get(file_name)
+            .unwrap_or_else(|| panic!("missing file named {}", file_name))
+    }
+
     pub fn get_ppm_or_panic(&self, ppm_name: &String) -> &Ppm {
         self.ppms
             .get(ppm_name)